@@ -0,0 +1,28 @@
+use crate::segment_tree::SumValue;
+
+// Abelian group under addition: a SumValue whose elements can also be subtracted
+pub trait AdditiveGroup: SumValue {
+    fn negate(self) -> Self;
+
+    fn minus(self, other: Self) -> Self {
+        self.plus(other.negate())
+    }
+}
+
+macro_rules! impl_additive_group {
+    ($($t:ty),*) => {
+        $(
+            impl AdditiveGroup for $t {
+                fn negate(self) -> Self {
+                    -self
+                }
+
+                fn minus(self, other: Self) -> Self {
+                    self - other
+                }
+            }
+        )*
+    };
+}
+
+impl_additive_group!(i8, i16, i32, i64, i128, isize, f32, f64);
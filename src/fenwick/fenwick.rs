@@ -0,0 +1,76 @@
+use super::additive_group::AdditiveGroup;
+
+// Point update, prefix query. Position i is stored at tree[i + 1].
+pub struct Fenwick<T: AdditiveGroup> {
+    tree: Vec<T>,
+}
+
+impl<T: AdditiveGroup> Fenwick<T> {
+    pub fn new(arr: &[T]) -> Self {
+        let mut tree = vec![T::zero(); arr.len() + 1];
+        for (pos, element) in arr.iter().enumerate() {
+            let node_id = pos + 1;
+            tree[node_id] = tree[node_id].plus(*element);
+            let parent_id = node_id + (node_id & node_id.wrapping_neg());
+            if parent_id < tree.len() {
+                tree[parent_id] = tree[parent_id].plus(tree[node_id]);
+            }
+        }
+        Self { tree }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn add(&mut self, pos: usize, delta: T) {
+        let mut node_id = pos + 1;
+        while node_id < self.tree.len() {
+            self.tree[node_id] = self.tree[node_id].plus(delta);
+            node_id += node_id & node_id.wrapping_neg();
+        }
+    }
+
+    // Sum of [0, pos]
+    pub fn prefix_sum(&self, pos: usize) -> T {
+        let mut result = T::zero();
+        let mut node_id = pos + 1;
+        while node_id > 0 {
+            result = result.plus(self.tree[node_id]);
+            node_id -= node_id & node_id.wrapping_neg();
+        }
+        result
+    }
+
+    pub fn range_sum(&self, left: usize, right: usize) -> T {
+        if left == 0 {
+            return self.prefix_sum(right);
+        }
+        self.prefix_sum(right).minus(self.prefix_sum(left - 1))
+    }
+}
+
+impl<T: AdditiveGroup + PartialOrd> Fenwick<T> {
+    // Smallest pos with prefix_sum(pos) >= prefix, or len() if there is none.
+    // Only meaningful when every element is non-negative.
+    pub fn lower_bound(&self, prefix: T) -> usize {
+        let mut pos = 0;
+        let mut remaining = prefix;
+        let mut step = 1;
+        while step * 2 < self.tree.len() {
+            step *= 2;
+        }
+        while step > 0 {
+            if pos + step < self.tree.len() && self.tree[pos + step] < remaining {
+                pos += step;
+                remaining = remaining.minus(self.tree[pos]);
+            }
+            step /= 2;
+        }
+        pos
+    }
+}
@@ -0,0 +1,67 @@
+use super::additive_group::AdditiveGroup;
+
+// Point update, rectangle query over a rows x cols grid
+pub struct Fenwick2D<T: AdditiveGroup> {
+    tree: Vec<Vec<T>>,
+}
+
+impl<T: AdditiveGroup> Fenwick2D<T> {
+    pub fn new(rows: usize, cols: usize) -> Self {
+        Self {
+            tree: vec![vec![T::zero(); cols + 1]; rows + 1],
+        }
+    }
+
+    pub fn from_grid(grid: &[Vec<T>]) -> Self {
+        let cols = grid.first().map_or(0, |row| row.len());
+        let mut fenwick = Self::new(grid.len(), cols);
+        for (row, values) in grid.iter().enumerate() {
+            for (col, value) in values.iter().enumerate() {
+                fenwick.add(row, col, *value);
+            }
+        }
+        fenwick
+    }
+
+    pub fn add(&mut self, row: usize, col: usize, delta: T) {
+        let mut row_id = row + 1;
+        while row_id < self.tree.len() {
+            let mut col_id = col + 1;
+            while col_id < self.tree[row_id].len() {
+                self.tree[row_id][col_id] = self.tree[row_id][col_id].plus(delta);
+                col_id += col_id & col_id.wrapping_neg();
+            }
+            row_id += row_id & row_id.wrapping_neg();
+        }
+    }
+
+    // Sum of the rectangle [0, row] x [0, col]
+    pub fn prefix_sum(&self, row: usize, col: usize) -> T {
+        let mut result = T::zero();
+        let mut row_id = row + 1;
+        while row_id > 0 {
+            let mut col_id = col + 1;
+            while col_id > 0 {
+                result = result.plus(self.tree[row_id][col_id]);
+                col_id -= col_id & col_id.wrapping_neg();
+            }
+            row_id -= row_id & row_id.wrapping_neg();
+        }
+        result
+    }
+
+    // Sum of the rectangle [row1, row2] x [col1, col2]
+    pub fn rect_sum(&self, row1: usize, col1: usize, row2: usize, col2: usize) -> T {
+        let mut result = self.prefix_sum(row2, col2);
+        if row1 > 0 {
+            result = result.minus(self.prefix_sum(row1 - 1, col2));
+        }
+        if col1 > 0 {
+            result = result.minus(self.prefix_sum(row2, col1 - 1));
+        }
+        if row1 > 0 && col1 > 0 {
+            result = result.plus(self.prefix_sum(row1 - 1, col1 - 1));
+        }
+        result
+    }
+}
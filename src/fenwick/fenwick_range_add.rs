@@ -0,0 +1,42 @@
+use super::additive_group::AdditiveGroup;
+use super::fenwick::Fenwick;
+
+// Range update, point query over the difference array
+pub struct FenwickRangeAdd<T: AdditiveGroup> {
+    diff: Fenwick<T>,
+}
+
+impl<T: AdditiveGroup> FenwickRangeAdd<T> {
+    pub fn new(arr: &[T]) -> Self {
+        let diff: Vec<T> = arr
+            .iter()
+            .enumerate()
+            .map(|(pos, element)| match pos {
+                0 => *element,
+                _ => element.minus(arr[pos - 1]),
+            })
+            .collect();
+        Self {
+            diff: Fenwick::new(&diff),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.diff.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.diff.is_empty()
+    }
+
+    pub fn add(&mut self, left: usize, right: usize, delta: T) {
+        self.diff.add(left, delta);
+        if right + 1 < self.diff.len() {
+            self.diff.add(right + 1, delta.negate());
+        }
+    }
+
+    pub fn get(&self, pos: usize) -> T {
+        self.diff.prefix_sum(pos)
+    }
+}
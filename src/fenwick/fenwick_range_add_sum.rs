@@ -0,0 +1,64 @@
+use super::additive_group::AdditiveGroup;
+use super::fenwick::Fenwick;
+
+// Range update, range query with two trees over the difference array d:
+// prefix_sum(pos) = (pos + 1) * sum(d[0..=pos]) - sum(i * d[i] for i in 0..=pos)
+pub struct FenwickRangeAddSum<T: AdditiveGroup> {
+    diff: Fenwick<T>,
+    weighted_diff: Fenwick<T>,
+}
+
+impl<T: AdditiveGroup> FenwickRangeAddSum<T> {
+    pub fn new(arr: &[T]) -> Self {
+        let diff: Vec<T> = arr
+            .iter()
+            .enumerate()
+            .map(|(pos, element)| match pos {
+                0 => *element,
+                _ => element.minus(arr[pos - 1]),
+            })
+            .collect();
+        let weighted_diff: Vec<T> = diff
+            .iter()
+            .enumerate()
+            .map(|(pos, element)| element.times(pos))
+            .collect();
+        Self {
+            diff: Fenwick::new(&diff),
+            weighted_diff: Fenwick::new(&weighted_diff),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.diff.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.diff.is_empty()
+    }
+
+    pub fn add(&mut self, left: usize, right: usize, delta: T) {
+        self.diff.add(left, delta);
+        self.weighted_diff.add(left, delta.times(left));
+        if right + 1 < self.diff.len() {
+            self.diff.add(right + 1, delta.negate());
+            self.weighted_diff
+                .add(right + 1, delta.times(right + 1).negate());
+        }
+    }
+
+    // Sum of [0, pos]
+    pub fn prefix_sum(&self, pos: usize) -> T {
+        self.diff
+            .prefix_sum(pos)
+            .times(pos + 1)
+            .minus(self.weighted_diff.prefix_sum(pos))
+    }
+
+    pub fn range_sum(&self, left: usize, right: usize) -> T {
+        if left == 0 {
+            return self.prefix_sum(right);
+        }
+        self.prefix_sum(right).minus(self.prefix_sum(left - 1))
+    }
+}
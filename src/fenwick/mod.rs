@@ -0,0 +1,12 @@
+pub mod additive_group;
+#[allow(clippy::module_inception)]
+pub mod fenwick;
+pub mod fenwick_2d;
+pub mod fenwick_range_add;
+pub mod fenwick_range_add_sum;
+
+pub use additive_group::AdditiveGroup;
+pub use fenwick::Fenwick;
+pub use fenwick_2d::Fenwick2D;
+pub use fenwick_range_add::FenwickRangeAdd;
+pub use fenwick_range_add_sum::FenwickRangeAddSum;
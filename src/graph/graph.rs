@@ -65,7 +65,7 @@ where
         let mut node_map_rev: Vec<N> = Vec::new();
         let mut node_count = 0;
         for node in nodes {
-            if node_map.get(node).is_none() {
+            if !node_map.contains_key(node) {
                 node_map.insert(node.clone(), node_count);
                 node_map_rev.push(node.clone());
                 node_count += 1;
@@ -106,7 +106,7 @@ where
         let mut node_map_rev: Vec<N> = Vec::new();
         let mut node_count = 0;
         for node in nodes {
-            if node_map.get(node).is_none() {
+            if !node_map.contains_key(node) {
                 node_map.insert(node.clone(), node_count);
                 node_map_rev.push(node.clone());
                 node_count += 1;
@@ -174,11 +174,11 @@ where
             }
         }
 
-        return infos;
+        infos
     }
 
-    fn _dfs<'a, InfoT: Clone>(
-        &'a self,
+    fn _dfs<InfoT: Clone>(
+        &self,
         start: usize,
         start_info: &InfoT,
         enter_fn: impl Fn(usize, &Vec<Option<InfoT>>) -> InfoT, // parent_id -> parent_id -> infos -> current_info
@@ -234,14 +234,14 @@ where
             }
         }
 
-        return infos;
+        infos
     }
 
     fn _has_cycle(&self) -> bool {
-        return false;
+        false
     }
     fn _has_cycle_bidir(&self) -> bool {
-        return false;
+        false
     }
 
     fn _is_tree(&self) -> bool {
@@ -257,7 +257,7 @@ where
         }
 
         let infos = self._bfs::<()>(0, (), |(), _| ());
-        return infos.into_iter().filter(|x| x.is_some()).count() == self.node_map_rev.len();
+        infos.into_iter().filter(|x| x.is_some()).count() == self.node_map_rev.len()
     }
 
     pub fn is_bidir(&self) -> bool {
@@ -281,10 +281,7 @@ where
     }
 
     fn _assert_categories(&self, bits: Vec<u8>, fn_name: &str) {
-        let satisfies = bits
-            .iter()
-            .map(|bit| self.category & bit > 0)
-            .fold(true, |x, y| x && y);
+        let satisfies = bits.iter().all(|bit| self.category & bit > 0);
         let bit_names: Vec<String> = bits
             .iter()
             .map(|bit| GraphCategoryBits::name_from_bit(*bit))
@@ -351,6 +348,7 @@ where
                 subtree_sz: None,
             }
         };
+        #[allow(clippy::panicking_overflow_checks)]
         let exit_fn = |node_id: usize, infos: &Vec<Option<TreeNode>>| -> TreeNode {
             let mut subtree_upnode_cnt = 0;
             let mut subtree_sz = 1;
@@ -425,7 +423,7 @@ where
             bit -= 1;
         }
 
-        return infos[common_ancestor].parent_id;
+        infos[common_ancestor].parent_id
     }
 
     pub fn common_ancestor(&self, node1: &N, node2: &N) -> &N {
@@ -442,7 +440,6 @@ where
         let infos = self.rooted_tree_infos.as_ref().unwrap();
 
         (0..n)
-            .into_iter()
             .filter(|v| infos[*v].parent_id != *v && infos[*v].subtree_upnode_cnt.unwrap() == 0)
             .map(|v| {
                 (
@@ -458,7 +455,7 @@ where
         for
     }*/
 
-    pub fn node_iter<'a>(&'a self, node: &'a N) -> impl Iterator<Item = (&N, &E)> + 'a {
+    pub fn node_iter<'a>(&'a self, node: &'a N) -> impl Iterator<Item = (&'a N, &'a E)> + 'a {
         self.nbs[self.node_map[node]]
             .iter()
             .map(|(node_id, distance)| (&self.node_map_rev[*node_id], distance))
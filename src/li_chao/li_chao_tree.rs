@@ -0,0 +1,165 @@
+use super::line::Line;
+use core::cmp;
+use core::mem;
+
+// Li Chao tree over a fixed, sorted set of query coordinates, stored in the same flat layout
+// as SegmentTreeBmp. Every node keeps the line that wins at the middle of its range among the
+// lines inserted there, so a point query checks the O(log n) nodes on the path to its leaf.
+pub struct LiChaoTree {
+    lines: Vec<Option<Line>>,
+    xs: Vec<i64>,
+    maximize: bool,
+}
+
+impl LiChaoTree {
+    fn _new(xs: &[i64], maximize: bool) -> Self {
+        assert!(
+            !xs.is_empty(),
+            "a Li Chao tree needs at least one coordinate"
+        );
+        let mut xs = xs.to_vec();
+        xs.sort();
+        xs.dedup();
+        Self {
+            lines: vec![None; xs.len() * 4],
+            xs,
+            maximize,
+        }
+    }
+
+    // Queries return the minimum over the inserted lines, `xs` are the only valid query points.
+    // Panics if `xs` is empty.
+    pub fn new_min(xs: &[i64]) -> Self {
+        Self::_new(xs, false)
+    }
+
+    pub fn new_max(xs: &[i64]) -> Self {
+        Self::_new(xs, true)
+    }
+
+    pub fn len(&self) -> usize {
+        self.xs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.xs.is_empty()
+    }
+
+    fn _better(&self, a: i128, b: i128) -> bool {
+        if self.maximize {
+            a > b
+        } else {
+            a < b
+        }
+    }
+
+    fn _add_line(&mut self, line: Line, node_id: usize, left: usize, right: usize) {
+        let mut line = line;
+        let mut node_line = match self.lines[node_id] {
+            Some(node_line) => node_line,
+            None => {
+                self.lines[node_id] = Some(line);
+                return;
+            }
+        };
+
+        let middle = (left + right) / 2;
+        let (x_left, x_middle) = (self.xs[left], self.xs[middle]);
+        let better_left = self._better(line.eval(x_left), node_line.eval(x_left));
+        let better_middle = self._better(line.eval(x_middle), node_line.eval(x_middle));
+        if better_middle {
+            mem::swap(&mut node_line, &mut line);
+            self.lines[node_id] = Some(node_line);
+        }
+        if left == right {
+            return;
+        }
+
+        // The two lines cross at most once, so the loser can only win on one side of middle
+        if better_left != better_middle {
+            self._add_line(line, node_id * 2 + 1, left, middle);
+        } else {
+            self._add_line(line, node_id * 2 + 2, middle + 1, right);
+        }
+    }
+
+    fn _add_segment(
+        &mut self,
+        line: Line,
+        node_id: usize,
+        left: usize,
+        right: usize,
+        left_update: usize,
+        right_update: usize,
+    ) {
+        if left_update > right_update {
+            return;
+        }
+
+        if left_update == left && right_update == right {
+            self._add_line(line, node_id, left, right);
+            return;
+        }
+
+        let middle = (left + right) / 2;
+
+        self._add_segment(
+            line,
+            node_id * 2 + 1,
+            left,
+            middle,
+            left_update,
+            cmp::min(right_update, middle),
+        );
+        self._add_segment(
+            line,
+            node_id * 2 + 2,
+            middle + 1,
+            right,
+            cmp::max(left_update, middle + 1),
+            right_update,
+        );
+    }
+
+    pub fn add_line(&mut self, line: Line) {
+        self._add_line(line, 0, 0, self.xs.len() - 1);
+    }
+
+    // Inserts `line` restricted to the coordinates within [x_left, x_right], in O(log^2 n)
+    pub fn add_segment(&mut self, line: Line, x_left: i64, x_right: i64) {
+        let left = self.xs.partition_point(|x| *x < x_left);
+        let right = self.xs.partition_point(|x| *x <= x_right);
+        if left < right {
+            self._add_segment(line, 0, 0, self.xs.len() - 1, left, right - 1);
+        }
+    }
+
+    // Best value at `x` among the lines covering it, None if there is none.
+    // Panics if `x` is not one of the coordinates given at construction.
+    pub fn query(&self, x: i64) -> Option<i128> {
+        let pos = self
+            .xs
+            .binary_search(&x)
+            .expect("x is not a tree coordinate");
+        let (mut node_id, mut left, mut right) = (0, 0, self.xs.len() - 1);
+        let mut result: Option<i128> = None;
+        loop {
+            if let Some(line) = self.lines[node_id] {
+                let value = line.eval(x);
+                if result.is_none_or(|best| self._better(value, best)) {
+                    result = Some(value);
+                }
+            }
+            if left == right {
+                return result;
+            }
+
+            let middle = (left + right) / 2;
+            if pos <= middle {
+                (node_id, right) = (node_id * 2 + 1, middle);
+            } else {
+                (node_id, left) = (node_id * 2 + 2, middle + 1);
+            }
+        }
+    }
+}
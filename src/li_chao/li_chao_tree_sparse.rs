@@ -0,0 +1,193 @@
+use super::line::Line;
+use core::cmp;
+use core::mem;
+
+// `line` is None on nodes that were only created on the way to a segment's nodes
+struct SparseNode {
+    line: Option<Line>,
+    left_node: Option<Box<SparseNode>>,
+    right_node: Option<Box<SparseNode>>,
+}
+
+// Li Chao tree over every integer of [lo, hi], nodes are only allocated on the paths to the
+// nodes lines are stored in, so memory is O(lines * log^2(hi - lo)) even for the whole i64
+// domain (O(lines * log(hi - lo)) with `add_line` only)
+pub struct LiChaoTreeSparse {
+    root: Option<Box<SparseNode>>,
+    lo: i64,
+    hi: i64,
+    maximize: bool,
+}
+
+// Floor of the midpoint, without overflowing at the ends of the i64 domain
+fn middle_of(left: i64, right: i64) -> i64 {
+    ((left as i128 + right as i128).div_euclid(2)) as i64
+}
+
+impl LiChaoTreeSparse {
+    fn _new(lo: i64, hi: i64, maximize: bool) -> Self {
+        assert!(lo <= hi, "empty domain [{}, {}]", lo, hi);
+        Self {
+            root: None,
+            lo,
+            hi,
+            maximize,
+        }
+    }
+
+    // Queries return the minimum over the inserted lines. Panics if lo > hi.
+    pub fn new_min(lo: i64, hi: i64) -> Self {
+        Self::_new(lo, hi, false)
+    }
+
+    pub fn new_max(lo: i64, hi: i64) -> Self {
+        Self::_new(lo, hi, true)
+    }
+
+    fn _better(maximize: bool, a: i128, b: i128) -> bool {
+        if maximize {
+            a > b
+        } else {
+            a < b
+        }
+    }
+
+    fn _add_line(
+        node: &mut Option<Box<SparseNode>>,
+        maximize: bool,
+        line: Line,
+        left: i64,
+        right: i64,
+    ) {
+        let node = node.get_or_insert_with(|| {
+            Box::new(SparseNode {
+                line: None,
+                left_node: None,
+                right_node: None,
+            })
+        });
+        let node_line = match node.line.as_mut() {
+            Some(node_line) => node_line,
+            None => {
+                node.line = Some(line);
+                return;
+            }
+        };
+
+        let mut line = line;
+        let middle = middle_of(left, right);
+        let better_left = Self::_better(maximize, line.eval(left), node_line.eval(left));
+        let better_middle = Self::_better(maximize, line.eval(middle), node_line.eval(middle));
+        if better_middle {
+            mem::swap(node_line, &mut line);
+        }
+        if left == right {
+            return;
+        }
+
+        // The two lines cross at most once, so the loser can only win on one side of middle
+        if better_left != better_middle {
+            Self::_add_line(&mut node.left_node, maximize, line, left, middle);
+        } else {
+            Self::_add_line(&mut node.right_node, maximize, line, middle + 1, right);
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn _add_segment(
+        node: &mut Option<Box<SparseNode>>,
+        maximize: bool,
+        line: Line,
+        left: i64,
+        right: i64,
+        left_update: i64,
+        right_update: i64,
+    ) {
+        if left_update > right_update {
+            return;
+        }
+
+        if left_update == left && right_update == right {
+            Self::_add_line(node, maximize, line, left, right);
+            return;
+        }
+
+        let middle = middle_of(left, right);
+
+        let node = node.get_or_insert_with(|| {
+            Box::new(SparseNode {
+                line: None,
+                left_node: None,
+                right_node: None,
+            })
+        });
+        Self::_add_segment(
+            &mut node.left_node,
+            maximize,
+            line,
+            left,
+            middle,
+            left_update,
+            cmp::min(right_update, middle),
+        );
+        Self::_add_segment(
+            &mut node.right_node,
+            maximize,
+            line,
+            middle + 1,
+            right,
+            cmp::max(left_update, middle + 1),
+            right_update,
+        );
+    }
+
+    pub fn add_line(&mut self, line: Line) {
+        Self::_add_line(&mut self.root, self.maximize, line, self.lo, self.hi);
+    }
+
+    // Inserts `line` restricted to [x_left, x_right], in O(log^2 (hi - lo))
+    pub fn add_segment(&mut self, line: Line, x_left: i64, x_right: i64) {
+        Self::_add_segment(
+            &mut self.root,
+            self.maximize,
+            line,
+            self.lo,
+            self.hi,
+            cmp::max(x_left, self.lo),
+            cmp::min(x_right, self.hi),
+        );
+    }
+
+    // Best value at `x` among the lines covering it, None if there is none or `x` is outside
+    // [lo, hi]
+    pub fn query(&self, x: i64) -> Option<i128> {
+        if x < self.lo || x > self.hi {
+            return None;
+        }
+
+        let mut node = self.root.as_ref();
+        let (mut left, mut right) = (self.lo, self.hi);
+        let mut result: Option<i128> = None;
+        while let Some(current) = node {
+            if let Some(line) = current.line {
+                let value = line.eval(x);
+                if result.is_none_or(|best| Self::_better(self.maximize, value, best)) {
+                    result = Some(value);
+                }
+            }
+            if left == right {
+                break;
+            }
+
+            let middle = middle_of(left, right);
+            if x <= middle {
+                node = current.left_node.as_ref();
+                right = middle;
+            } else {
+                node = current.right_node.as_ref();
+                left = middle + 1;
+            }
+        }
+        result
+    }
+}
@@ -0,0 +1,16 @@
+// y = slope * x + intercept, evaluated in i128 so that any i64 inputs fit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Line {
+    pub slope: i64,
+    pub intercept: i64,
+}
+
+impl Line {
+    pub fn new(slope: i64, intercept: i64) -> Self {
+        Self { slope, intercept }
+    }
+
+    pub fn eval(&self, x: i64) -> i128 {
+        (self.slope as i128) * (x as i128) + (self.intercept as i128)
+    }
+}
@@ -0,0 +1,7 @@
+pub mod li_chao_tree;
+pub mod li_chao_tree_sparse;
+pub mod line;
+
+pub use li_chao_tree::LiChaoTree;
+pub use li_chao_tree_sparse::LiChaoTreeSparse;
+pub use line::Line;
@@ -1,6 +1,49 @@
+pub mod fenwick;
+pub mod li_chao;
 pub mod segment_tree;
+pub mod sparse_table;
+pub mod treap;
+pub mod wavelet_matrix;
 
+pub use fenwick::AdditiveGroup;
+pub use fenwick::Fenwick;
+pub use fenwick::Fenwick2D;
+pub use fenwick::FenwickRangeAdd;
+pub use fenwick::FenwickRangeAddSum;
+pub use li_chao::LiChaoTree;
+pub use li_chao::LiChaoTreeSparse;
+pub use li_chao::Line;
+pub use segment_tree::AdditiveDelta;
+pub use segment_tree::AffineDelta;
+pub use segment_tree::AffineNode;
+pub use segment_tree::AndNode;
+pub use segment_tree::AssignDelta;
+pub use segment_tree::AssignNode;
+pub use segment_tree::AssignValue;
+pub use segment_tree::BeatsDelta;
+pub use segment_tree::BeatsNode;
+pub use segment_tree::BeatsValue;
+pub use segment_tree::Checked;
+pub use segment_tree::CommutativeDelta;
+pub use segment_tree::CompressedSegmentTree;
+pub use segment_tree::GcdNode;
+pub use segment_tree::MaxNode;
+pub use segment_tree::MaxSubarrayNode;
+pub use segment_tree::MaxSubarrayValue;
+pub use segment_tree::MergeableLeaf;
+pub use segment_tree::MinNode;
 pub use segment_tree::SegmentTree;
+pub use segment_tree::SegmentTree2D;
 pub use segment_tree::SegmentTreeBmp;
+pub use segment_tree::SegmentTreeBottomUp;
+pub use segment_tree::SegmentTreeError;
+pub use segment_tree::SegmentTreeFn;
+pub use segment_tree::SegmentTreePersistent;
+pub use segment_tree::SegmentTreeSparse;
 pub use segment_tree::SegmentTreeState;
 pub use segment_tree::SumNode;
+pub use segment_tree::SumValue;
+pub use sparse_table::DisjointSparseTable;
+pub use sparse_table::SparseTable;
+pub use treap::Treap;
+pub use wavelet_matrix::WaveletMatrix;
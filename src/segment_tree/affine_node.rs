@@ -0,0 +1,117 @@
+use super::segment_tree::SegmentTreeState;
+
+// Pending tag `x -> mul * x + add (mod MOD)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AffineDelta {
+    pub mul: u64,
+    pub add: u64,
+}
+
+impl AffineDelta {
+    pub fn new(mul: u64, add: u64) -> Self {
+        Self { mul, add }
+    }
+
+    fn then<const MOD: u64>(&mut self, delta: &Self) {
+        self.mul = mul_mod::<MOD>(self.mul, delta.mul);
+        self.add = add_mod::<MOD>(mul_mod::<MOD>(self.add, delta.mul), delta.add);
+    }
+}
+
+fn add_mod<const MOD: u64>(a: u64, b: u64) -> u64 {
+    ((a as u128 + b as u128) % (MOD as u128)) as u64
+}
+
+fn mul_mod<const MOD: u64>(a: u64, b: u64) -> u64 {
+    ((a as u128) * (b as u128) % (MOD as u128)) as u64
+}
+
+// Range sum modulo MOD with range affine updates
+#[derive(Debug, Clone)]
+pub struct AffineNode<const MOD: u64> {
+    sum: u64,
+    delta: AffineDelta,
+}
+
+impl<const MOD: u64> SegmentTreeState for AffineNode<MOD> {
+    type ArrayT = u64;
+    type LeafT = u64;
+    type DeltaT = AffineDelta;
+
+    fn reduce(a: Self::LeafT, b: Self::LeafT) -> Self::LeafT {
+        add_mod::<MOD>(a, b)
+    }
+
+    fn identity() -> Self::LeafT {
+        0
+    }
+
+    fn compose_delta(delta: &Self::DeltaT, next_delta: &Self::DeltaT) -> Self::DeltaT {
+        let mut composed = *delta;
+        composed.then::<MOD>(next_delta);
+        composed
+    }
+
+    fn apply_delta_to_value(value: Self::LeafT, delta: &Self::DeltaT, len: usize) -> Self::LeafT {
+        add_mod::<MOD>(
+            mul_mod::<MOD>(value, delta.mul),
+            mul_mod::<MOD>(delta.add, len as u64),
+        )
+    }
+
+    fn new() -> Self {
+        Self {
+            sum: 0,
+            delta: AffineDelta::new(1, 0),
+        }
+    }
+
+    fn new_leaf(element: &Self::ArrayT, _leaf_pos: usize) -> Self {
+        Self {
+            sum: element % MOD,
+            delta: AffineDelta::new(1, 0),
+        }
+    }
+
+    fn new_range(element: &Self::ArrayT, left: usize, right: usize) -> Self {
+        Self {
+            sum: mul_mod::<MOD>(*element, (right - left + 1) as u64),
+            delta: AffineDelta::new(1, 0),
+        }
+    }
+
+    fn new_with_children(left_child: &Self, right_child: &Self) -> Self {
+        Self {
+            sum: Self::reduce(left_child.sum, right_child.sum),
+            delta: AffineDelta::new(1, 0),
+        }
+    }
+
+    fn get_delta(&self) -> Self::DeltaT {
+        self.delta
+    }
+
+    fn update_delta(&mut self, delta: &Self::DeltaT) {
+        self.delta.then::<MOD>(delta);
+    }
+
+    fn get_value(&self) -> Self::LeafT {
+        self.sum
+    }
+
+    fn update_value(&mut self, left_child: &Self, right_child: &Self) {
+        self.sum = Self::reduce(left_child.sum, right_child.sum);
+    }
+
+    fn push(&mut self, left_child: &mut Self, right_child: &mut Self, len: usize) {
+        left_child.delta.then::<MOD>(&self.delta);
+        right_child.delta.then::<MOD>(&self.delta);
+        self.sum = Self::apply_delta_to_value(self.sum, &self.delta, len);
+        self.delta = AffineDelta::new(1, 0);
+    }
+
+    fn push_leaf(&mut self) {
+        self.sum = Self::apply_delta_to_value(self.sum, &self.delta, 1);
+        self.delta = AffineDelta::new(1, 0);
+    }
+}
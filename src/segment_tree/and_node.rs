@@ -0,0 +1,94 @@
+use super::segment_tree::MergeableLeaf;
+use super::segment_tree::SegmentTreeState;
+
+// Range bitwise and with range assign
+#[derive(Debug, Clone)]
+pub struct AndNode {
+    and: u64,
+    delta: Option<u64>,
+}
+
+impl SegmentTreeState for AndNode {
+    type ArrayT = u64;
+    type LeafT = u64;
+    type DeltaT = Option<u64>;
+
+    fn reduce(a: Self::LeafT, b: Self::LeafT) -> Self::LeafT {
+        a & b
+    }
+
+    fn identity() -> Self::LeafT {
+        u64::MAX
+    }
+
+    fn compose_delta(delta: &Self::DeltaT, next_delta: &Self::DeltaT) -> Self::DeltaT {
+        next_delta.or(*delta)
+    }
+
+    // x & ... & x = x, whatever the length of the assigned range
+    fn apply_delta_to_value(value: Self::LeafT, delta: &Self::DeltaT, _len: usize) -> Self::LeafT {
+        delta.unwrap_or(value)
+    }
+
+    fn new() -> Self {
+        Self {
+            and: Self::identity(),
+            delta: None,
+        }
+    }
+
+    fn new_leaf(element: &Self::ArrayT, _leaf_pos: usize) -> Self {
+        Self {
+            and: *element,
+            delta: None,
+        }
+    }
+
+    fn new_range(element: &Self::ArrayT, _left: usize, _right: usize) -> Self {
+        Self::new_leaf(element, 0)
+    }
+
+    fn new_with_children(left_child: &Self, right_child: &Self) -> Self {
+        Self {
+            and: Self::reduce(left_child.and, right_child.and),
+            delta: None,
+        }
+    }
+
+    fn get_delta(&self) -> Self::DeltaT {
+        self.delta
+    }
+
+    fn update_delta(&mut self, delta: &Self::DeltaT) {
+        self.delta = Self::compose_delta(&self.delta, delta);
+    }
+
+    fn get_value(&self) -> Self::LeafT {
+        self.and
+    }
+
+    fn update_value(&mut self, left_child: &Self, right_child: &Self) {
+        self.and = Self::reduce(left_child.and, right_child.and);
+    }
+
+    fn push(&mut self, left_child: &mut Self, right_child: &mut Self, len: usize) {
+        left_child.update_delta(&self.delta);
+        right_child.update_delta(&self.delta);
+        self.and = Self::apply_delta_to_value(self.and, &self.delta, len);
+        self.delta = None;
+    }
+
+    fn push_leaf(&mut self) {
+        self.and = Self::apply_delta_to_value(self.and, &self.delta, 1);
+        self.delta = None;
+    }
+}
+
+impl MergeableLeaf for AndNode {
+    fn new_leaf_from_value(value: Self::LeafT) -> Self {
+        Self {
+            and: value,
+            delta: None,
+        }
+    }
+}
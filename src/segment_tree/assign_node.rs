@@ -0,0 +1,155 @@
+use super::segment_tree::SegmentTreeState;
+use core::cmp;
+
+// Pending tag `x -> assign.unwrap_or(x) + add`, an assignment overrides any add queued before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AssignDelta {
+    pub assign: Option<i64>,
+    pub add: i64,
+}
+
+impl AssignDelta {
+    pub fn set(value: i64) -> Self {
+        Self {
+            assign: Some(value),
+            add: 0,
+        }
+    }
+
+    pub fn add(delta: i64) -> Self {
+        Self {
+            assign: None,
+            add: delta,
+        }
+    }
+
+    fn then(&mut self, delta: &Self) {
+        if delta.assign.is_some() {
+            self.assign = delta.assign;
+            self.add = delta.add;
+        } else {
+            self.add += delta.add;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AssignValue {
+    pub sum: i64,
+    pub min: i64,
+    pub max: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct AssignNode {
+    value: AssignValue,
+    delta: AssignDelta,
+}
+
+impl SegmentTreeState for AssignNode {
+    type ArrayT = i64;
+    type LeafT = AssignValue;
+    type DeltaT = AssignDelta;
+
+    fn reduce(a: Self::LeafT, b: Self::LeafT) -> Self::LeafT {
+        AssignValue {
+            sum: a.sum + b.sum,
+            min: cmp::min(a.min, b.min),
+            max: cmp::max(a.max, b.max),
+        }
+    }
+
+    fn identity() -> Self::LeafT {
+        AssignValue {
+            sum: 0,
+            min: i64::MAX,
+            max: i64::MIN,
+        }
+    }
+
+    fn compose_delta(delta: &Self::DeltaT, next_delta: &Self::DeltaT) -> Self::DeltaT {
+        let mut composed = *delta;
+        composed.then(next_delta);
+        composed
+    }
+
+    fn apply_delta_to_value(value: Self::LeafT, delta: &Self::DeltaT, len: usize) -> Self::LeafT {
+        let value = match delta.assign {
+            Some(assign) => AssignValue {
+                sum: assign * (len as i64),
+                min: assign,
+                max: assign,
+            },
+            None => value,
+        };
+        AssignValue {
+            sum: value.sum + delta.add * (len as i64),
+            min: value.min + delta.add,
+            max: value.max + delta.add,
+        }
+    }
+
+    fn new() -> Self {
+        Self {
+            value: Self::identity(),
+            delta: AssignDelta::add(0),
+        }
+    }
+
+    fn new_leaf(element: &Self::ArrayT, _leaf_pos: usize) -> Self {
+        Self {
+            value: AssignValue {
+                sum: *element,
+                min: *element,
+                max: *element,
+            },
+            delta: AssignDelta::add(0),
+        }
+    }
+
+    fn new_range(element: &Self::ArrayT, left: usize, right: usize) -> Self {
+        Self {
+            value: AssignValue {
+                sum: element * ((right - left + 1) as i64),
+                min: *element,
+                max: *element,
+            },
+            delta: AssignDelta::add(0),
+        }
+    }
+
+    fn new_with_children(left_child: &Self, right_child: &Self) -> Self {
+        Self {
+            value: Self::reduce(left_child.value, right_child.value),
+            delta: AssignDelta::add(0),
+        }
+    }
+
+    fn get_delta(&self) -> Self::DeltaT {
+        self.delta
+    }
+
+    fn update_delta(&mut self, delta: &Self::DeltaT) {
+        self.delta.then(delta);
+    }
+
+    fn get_value(&self) -> Self::LeafT {
+        self.value
+    }
+
+    fn update_value(&mut self, left_child: &Self, right_child: &Self) {
+        self.value = Self::reduce(left_child.value, right_child.value);
+    }
+
+    fn push(&mut self, left_child: &mut Self, right_child: &mut Self, len: usize) {
+        left_child.delta.then(&self.delta);
+        right_child.delta.then(&self.delta);
+        self.value = Self::apply_delta_to_value(self.value, &self.delta, len);
+        self.delta = AssignDelta::add(0);
+    }
+
+    fn push_leaf(&mut self) {
+        self.value = Self::apply_delta_to_value(self.value, &self.delta, 1);
+        self.delta = AssignDelta::add(0);
+    }
+}
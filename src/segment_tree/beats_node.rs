@@ -0,0 +1,251 @@
+use super::segment_tree::SegmentTreeState;
+use core::cmp;
+
+// Pending tag `x -> min(max(x + add, lo), hi)` with `lo <= hi`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BeatsDelta {
+    pub add: i64,
+    pub lo: i64,
+    pub hi: i64,
+}
+
+impl BeatsDelta {
+    pub fn add(delta: i64) -> Self {
+        Self {
+            add: delta,
+            lo: i64::MIN,
+            hi: i64::MAX,
+        }
+    }
+
+    pub fn chmin(value: i64) -> Self {
+        Self {
+            add: 0,
+            lo: i64::MIN,
+            hi: value,
+        }
+    }
+
+    pub fn chmax(value: i64) -> Self {
+        Self {
+            add: 0,
+            lo: value,
+            hi: i64::MAX,
+        }
+    }
+
+    fn eval(&self, x: i64) -> i64 {
+        cmp::min(cmp::max(x + self.add, self.lo), self.hi)
+    }
+
+    fn then(&mut self, delta: &Self) {
+        let shift = |bound: i64| {
+            if bound == i64::MIN || bound == i64::MAX {
+                bound
+            } else {
+                bound + delta.add
+            }
+        };
+        let hi = cmp::min(cmp::max(shift(self.hi), delta.lo), delta.hi);
+        self.lo = cmp::min(cmp::max(shift(self.lo), delta.lo), hi);
+        self.hi = hi;
+        self.add += delta.add;
+    }
+}
+
+// Strict second extremes are i64::MIN / i64::MAX when the range holds a single distinct value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BeatsValue {
+    pub sum: i64,
+    pub max: i64,
+    pub second_max: i64,
+    pub max_count: usize,
+    pub min: i64,
+    pub second_min: i64,
+    pub min_count: usize,
+}
+
+// Range sum/min/max with range add, chmin and chmax (segment tree beats)
+#[derive(Debug, Clone)]
+pub struct BeatsNode {
+    value: BeatsValue,
+    delta: BeatsDelta,
+    // Kept in the node because can_absorb needs the value with the pending delta applied
+    len: usize,
+}
+
+impl SegmentTreeState for BeatsNode {
+    type ArrayT = i64;
+    type LeafT = BeatsValue;
+    type DeltaT = BeatsDelta;
+
+    fn reduce(a: Self::LeafT, b: Self::LeafT) -> Self::LeafT {
+        let (max, second_max, max_count) = match a.max.cmp(&b.max) {
+            cmp::Ordering::Greater => (a.max, cmp::max(a.second_max, b.max), a.max_count),
+            cmp::Ordering::Less => (b.max, cmp::max(a.max, b.second_max), b.max_count),
+            cmp::Ordering::Equal => (
+                a.max,
+                cmp::max(a.second_max, b.second_max),
+                a.max_count + b.max_count,
+            ),
+        };
+        let (min, second_min, min_count) = match a.min.cmp(&b.min) {
+            cmp::Ordering::Less => (a.min, cmp::min(a.second_min, b.min), a.min_count),
+            cmp::Ordering::Greater => (b.min, cmp::min(a.min, b.second_min), b.min_count),
+            cmp::Ordering::Equal => (
+                a.min,
+                cmp::min(a.second_min, b.second_min),
+                a.min_count + b.min_count,
+            ),
+        };
+        BeatsValue {
+            sum: a.sum + b.sum,
+            max,
+            second_max,
+            max_count,
+            min,
+            second_min,
+            min_count,
+        }
+    }
+
+    fn identity() -> Self::LeafT {
+        BeatsValue {
+            sum: 0,
+            max: i64::MIN,
+            second_max: i64::MIN,
+            max_count: 0,
+            min: i64::MAX,
+            second_min: i64::MAX,
+            min_count: 0,
+        }
+    }
+
+    fn compose_delta(delta: &Self::DeltaT, next_delta: &Self::DeltaT) -> Self::DeltaT {
+        let mut composed = *delta;
+        composed.then(next_delta);
+        composed
+    }
+
+    // Only valid when `delta` moves nothing but the minimum and maximum elements, which
+    // `can_absorb` guarantees for every tag that reaches a node
+    fn apply_delta_to_value(value: Self::LeafT, delta: &Self::DeltaT, len: usize) -> Self::LeafT {
+        if len == 0 {
+            return value;
+        }
+        if value.min == value.max {
+            let x = delta.eval(value.min);
+            return BeatsValue {
+                sum: x * (len as i64),
+                max: x,
+                min: x,
+                ..value
+            };
+        }
+
+        let shift = |x: i64| {
+            if x == i64::MIN || x == i64::MAX {
+                x
+            } else {
+                x + delta.add
+            }
+        };
+        let min = delta.eval(value.min);
+        let max = delta.eval(value.max);
+        let middle_count = len - value.min_count - value.max_count;
+        BeatsValue {
+            sum: value.sum
+                + delta.add * (middle_count as i64)
+                + (min - value.min) * (value.min_count as i64)
+                + (max - value.max) * (value.max_count as i64),
+            max,
+            second_max: if value.second_max == value.min {
+                min
+            } else {
+                shift(value.second_max)
+            },
+            max_count: value.max_count,
+            min,
+            second_min: if value.second_min == value.max {
+                max
+            } else {
+                shift(value.second_min)
+            },
+            min_count: value.min_count,
+        }
+    }
+
+    fn new() -> Self {
+        Self {
+            value: Self::identity(),
+            delta: BeatsDelta::add(0),
+            len: 0,
+        }
+    }
+
+    fn new_leaf(element: &Self::ArrayT, _leaf_pos: usize) -> Self {
+        Self::new_range(element, 0, 0)
+    }
+
+    fn new_range(element: &Self::ArrayT, left: usize, right: usize) -> Self {
+        let len = right - left + 1;
+        Self {
+            value: BeatsValue {
+                sum: element * (len as i64),
+                max: *element,
+                second_max: i64::MIN,
+                max_count: len,
+                min: *element,
+                second_min: i64::MAX,
+                min_count: len,
+            },
+            delta: BeatsDelta::add(0),
+            len,
+        }
+    }
+
+    fn new_with_children(left_child: &Self, right_child: &Self) -> Self {
+        Self {
+            value: Self::reduce(left_child.value, right_child.value),
+            delta: BeatsDelta::add(0),
+            len: left_child.len + right_child.len,
+        }
+    }
+
+    fn get_delta(&self) -> Self::DeltaT {
+        self.delta
+    }
+
+    fn update_delta(&mut self, delta: &Self::DeltaT) {
+        self.delta.then(delta);
+    }
+
+    fn can_absorb(&self, delta: &Self::DeltaT) -> bool {
+        let value = Self::apply_delta_to_value(self.value, &self.delta, self.len);
+        (delta.hi == i64::MAX
+            || value.second_max == i64::MIN
+            || delta.hi > value.second_max + delta.add)
+            && (delta.lo == i64::MIN
+                || value.second_min == i64::MAX
+                || delta.lo < value.second_min + delta.add)
+    }
+
+    fn get_value(&self) -> Self::LeafT {
+        self.value
+    }
+
+    fn update_value(&mut self, left_child: &Self, right_child: &Self) {
+        self.value = Self::reduce(left_child.value, right_child.value);
+    }
+
+    fn push(&mut self, left_child: &mut Self, right_child: &mut Self, _len: usize) {
+        left_child.delta.then(&self.delta);
+        right_child.delta.then(&self.delta);
+        self.push_leaf();
+    }
+
+    fn push_leaf(&mut self) {
+        self.value = Self::apply_delta_to_value(self.value, &self.delta, self.len);
+        self.delta = BeatsDelta::add(0);
+    }
+}
@@ -0,0 +1,46 @@
+use super::sum_value::SumValue;
+
+// Sum that becomes None once any addition or multiplication along the way overflows and
+// stays None (a poisoned node is only cleared by `set`). With non-negative values this means
+// `query` is None exactly when the true sum of the range does not fit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checked<T>(Option<T>);
+
+impl<T> Checked<T> {
+    pub fn new(value: T) -> Self {
+        Self(Some(value))
+    }
+
+    pub fn get(self) -> Option<T> {
+        self.0
+    }
+}
+
+macro_rules! impl_sum_value_checked {
+    ($($t:ty),*) => {
+        $(
+            impl SumValue for Checked<$t> {
+                fn zero() -> Self {
+                    Checked(Some(0))
+                }
+
+                fn plus(self, other: Self) -> Self {
+                    match (self.0, other.0) {
+                        (Some(a), Some(b)) => Checked(a.checked_add(b)),
+                        _ => Checked(None),
+                    }
+                }
+
+                fn times(self, n: usize) -> Self {
+                    match (self.0, <$t>::try_from(n)) {
+                        (Some(0), _) => Checked(Some(0)),
+                        (Some(value), Ok(n)) => Checked(value.checked_mul(n)),
+                        _ => Checked(None),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_sum_value_checked!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
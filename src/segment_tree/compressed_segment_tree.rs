@@ -0,0 +1,80 @@
+use super::segment_tree::SegmentTreeState;
+use super::segment_tree_bumped::SegmentTreeBmp;
+
+// SegmentTreeBmp over a sorted table of sparse keys: a key range [key_lo, key_hi] covers every
+// key of the table inside it, whether or not the bounds themselves are in the table. Positions
+// seen by the nodes (e.g. in MinNode results) are indices into `keys()`.
+pub struct CompressedSegmentTree<K: Ord, NodeT: SegmentTreeState> {
+    keys: Vec<K>,
+    tree: SegmentTreeBmp<NodeT>,
+}
+
+impl<K: Ord + Clone, NodeT: SegmentTreeState> CompressedSegmentTree<K, NodeT> {
+    // `arr[i]` is the initial element of `keys[i]`, keys can come in any order but must be
+    // distinct. Panics on empty input like SegmentTreeBmp::new.
+    pub fn new(keys: &[K], arr: &[NodeT::ArrayT]) -> Self
+    where
+        NodeT::ArrayT: Clone,
+    {
+        assert_eq!(keys.len(), arr.len(), "keys and elements differ in length");
+        let mut order: Vec<usize> = (0..keys.len()).collect();
+        order.sort_by(|a, b| keys[*a].cmp(&keys[*b]));
+        assert!(
+            order.windows(2).all(|pair| keys[pair[0]] != keys[pair[1]]),
+            "duplicate key"
+        );
+
+        let sorted_arr: Vec<NodeT::ArrayT> = order.iter().map(|i| arr[*i].clone()).collect();
+        Self {
+            keys: order.iter().map(|i| keys[*i].clone()).collect(),
+            tree: SegmentTreeBmp::new(&sorted_arr),
+        }
+    }
+}
+
+impl<K: Ord, NodeT: SegmentTreeState> CompressedSegmentTree<K, NodeT> {
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    // The key table in increasing order
+    pub fn keys(&self) -> &[K] {
+        &self.keys
+    }
+
+    // Indices of the keys within [key_lo, key_hi], None if there is no such key
+    fn _index_range(&self, key_lo: &K, key_hi: &K) -> Option<(usize, usize)> {
+        let left = self.keys.partition_point(|key| key < key_lo);
+        let right = self.keys.partition_point(|key| key <= key_hi);
+        if left < right {
+            Some((left, right - 1))
+        } else {
+            None
+        }
+    }
+
+    // Does nothing if no key of the table is within [key_lo, key_hi]
+    pub fn update(&mut self, key_lo: &K, key_hi: &K, delta: &NodeT::DeltaT) {
+        if let Some((left, right)) = self._index_range(key_lo, key_hi) {
+            self.tree.update(left, right, delta);
+        }
+    }
+
+    // `NodeT::identity()` if no key of the table is within [key_lo, key_hi]
+    pub fn query(&self, key_lo: &K, key_hi: &K) -> NodeT::LeafT {
+        match self._index_range(key_lo, key_hi) {
+            Some((left, right)) => self.tree.query(left, right),
+            None => NodeT::identity(),
+        }
+    }
+
+    // None if `key` is not in the table
+    pub fn get(&mut self, key: &K) -> Option<NodeT::LeafT> {
+        let pos = self.keys.binary_search(key).ok()?;
+        Some(self.tree.get(pos))
+    }
+}
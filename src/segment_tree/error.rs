@@ -0,0 +1,74 @@
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SegmentTreeError {
+    EmptyInput,
+    InvalidRange {
+        left: usize,
+        right: usize,
+    },
+    IndexOutOfBounds {
+        index: usize,
+        len: usize,
+    },
+    DomainTooLarge {
+        lo: i64,
+        hi: i64,
+    },
+    RaggedGrid {
+        row: usize,
+        len: usize,
+        expected: usize,
+    },
+}
+
+impl fmt::Display for SegmentTreeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SegmentTreeError::EmptyInput => {
+                write!(f, "cannot build a segment tree over an empty array")
+            }
+            SegmentTreeError::InvalidRange { left, right } => {
+                write!(
+                    f,
+                    "invalid range [{}, {}]: left is greater than right",
+                    left, right
+                )
+            }
+            SegmentTreeError::IndexOutOfBounds { index, len } => {
+                write!(f, "index {} is out of bounds for length {}", index, len)
+            }
+            SegmentTreeError::DomainTooLarge { lo, hi } => {
+                write!(
+                    f,
+                    "domain [{}, {}] has too many positions for a usize",
+                    lo, hi
+                )
+            }
+            SegmentTreeError::RaggedGrid { row, len, expected } => {
+                write!(
+                    f,
+                    "row {} has length {} but the first row has length {}",
+                    row, len, expected
+                )
+            }
+        }
+    }
+}
+
+impl Error for SegmentTreeError {}
+
+pub(crate) fn check_range(left: usize, right: usize, len: usize) -> Result<(), SegmentTreeError> {
+    if left > right {
+        return Err(SegmentTreeError::InvalidRange { left, right });
+    }
+    check_index(right, len)
+}
+
+pub(crate) fn check_index(index: usize, len: usize) -> Result<(), SegmentTreeError> {
+    if index >= len {
+        return Err(SegmentTreeError::IndexOutOfBounds { index, len });
+    }
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use super::segment_tree::MergeableLeaf;
+use super::segment_tree::SegmentTreeState;
+
+// Range gcd with range assign, gcd(0, x) = x makes 0 the identity
+#[derive(Debug, Clone)]
+pub struct GcdNode {
+    gcd: u64,
+    delta: Option<u64>,
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+impl SegmentTreeState for GcdNode {
+    type ArrayT = u64;
+    type LeafT = u64;
+    type DeltaT = Option<u64>;
+
+    fn reduce(a: Self::LeafT, b: Self::LeafT) -> Self::LeafT {
+        gcd(a, b)
+    }
+
+    fn identity() -> Self::LeafT {
+        0
+    }
+
+    fn compose_delta(delta: &Self::DeltaT, next_delta: &Self::DeltaT) -> Self::DeltaT {
+        next_delta.or(*delta)
+    }
+
+    // gcd(x, ..., x) = x, whatever the length of the assigned range
+    fn apply_delta_to_value(value: Self::LeafT, delta: &Self::DeltaT, _len: usize) -> Self::LeafT {
+        delta.unwrap_or(value)
+    }
+
+    fn new() -> Self {
+        Self {
+            gcd: Self::identity(),
+            delta: None,
+        }
+    }
+
+    fn new_leaf(element: &Self::ArrayT, _leaf_pos: usize) -> Self {
+        Self {
+            gcd: *element,
+            delta: None,
+        }
+    }
+
+    fn new_range(element: &Self::ArrayT, _left: usize, _right: usize) -> Self {
+        Self::new_leaf(element, 0)
+    }
+
+    fn new_with_children(left_child: &Self, right_child: &Self) -> Self {
+        Self {
+            gcd: Self::reduce(left_child.gcd, right_child.gcd),
+            delta: None,
+        }
+    }
+
+    fn get_delta(&self) -> Self::DeltaT {
+        self.delta
+    }
+
+    fn update_delta(&mut self, delta: &Self::DeltaT) {
+        self.delta = Self::compose_delta(&self.delta, delta);
+    }
+
+    fn get_value(&self) -> Self::LeafT {
+        self.gcd
+    }
+
+    fn update_value(&mut self, left_child: &Self, right_child: &Self) {
+        self.gcd = Self::reduce(left_child.gcd, right_child.gcd);
+    }
+
+    fn push(&mut self, left_child: &mut Self, right_child: &mut Self, len: usize) {
+        left_child.update_delta(&self.delta);
+        right_child.update_delta(&self.delta);
+        self.gcd = Self::apply_delta_to_value(self.gcd, &self.delta, len);
+        self.delta = None;
+    }
+
+    fn push_leaf(&mut self) {
+        self.gcd = Self::apply_delta_to_value(self.gcd, &self.delta, 1);
+        self.delta = None;
+    }
+}
+
+impl MergeableLeaf for GcdNode {
+    fn new_leaf_from_value(value: Self::LeafT) -> Self {
+        Self {
+            gcd: value,
+            delta: None,
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use super::segment_tree::CommutativeDelta;
+use super::segment_tree::MergeableLeaf;
+use super::segment_tree::SegmentTreeState;
+
+// Range maximum with range add; the value is (maximum, leftmost position of the maximum)
+#[derive(Debug, Clone)]
+pub struct MaxNode {
+    max: i64,
+    pos: usize,
+    delta: i64,
+}
+
+impl SegmentTreeState for MaxNode {
+    type ArrayT = i64;
+    type LeafT = (i64, usize);
+    type DeltaT = i64;
+
+    fn reduce(a: Self::LeafT, b: Self::LeafT) -> Self::LeafT {
+        if b.0 > a.0 || (b.0 == a.0 && b.1 < a.1) {
+            b
+        } else {
+            a
+        }
+    }
+
+    fn identity() -> Self::LeafT {
+        (i64::MIN, usize::MAX)
+    }
+
+    fn compose_delta(delta: &Self::DeltaT, next_delta: &Self::DeltaT) -> Self::DeltaT {
+        delta + next_delta
+    }
+
+    fn apply_delta_to_value(value: Self::LeafT, delta: &Self::DeltaT, _len: usize) -> Self::LeafT {
+        (value.0 + delta, value.1)
+    }
+
+    fn new() -> Self {
+        Self {
+            max: i64::MIN,
+            pos: usize::MAX,
+            delta: 0,
+        }
+    }
+
+    fn new_leaf(element: &Self::ArrayT, leaf_pos: usize) -> Self {
+        Self {
+            max: *element,
+            pos: leaf_pos,
+            delta: 0,
+        }
+    }
+
+    fn new_range(element: &Self::ArrayT, left: usize, _right: usize) -> Self {
+        Self {
+            max: *element,
+            pos: left,
+            delta: 0,
+        }
+    }
+
+    fn new_with_children(left_child: &Self, right_child: &Self) -> Self {
+        let (max, pos) = Self::reduce(left_child.get_value(), right_child.get_value());
+        Self { max, pos, delta: 0 }
+    }
+
+    fn get_delta(&self) -> Self::DeltaT {
+        self.delta
+    }
+
+    fn update_delta(&mut self, delta: &Self::DeltaT) {
+        self.delta += delta;
+    }
+
+    fn get_value(&self) -> Self::LeafT {
+        (self.max, self.pos)
+    }
+
+    fn update_value(&mut self, left_child: &Self, right_child: &Self) {
+        (self.max, self.pos) = Self::reduce(left_child.get_value(), right_child.get_value());
+    }
+
+    fn push(&mut self, left_child: &mut Self, right_child: &mut Self, _len: usize) {
+        self.max += self.delta;
+        left_child.delta += self.delta;
+        right_child.delta += self.delta;
+        self.delta = 0;
+    }
+
+    fn push_leaf(&mut self) {
+        self.max += self.delta;
+        self.delta = 0;
+    }
+}
+
+impl CommutativeDelta for MaxNode {}
+
+impl MergeableLeaf for MaxNode {
+    fn new_leaf_from_value(value: Self::LeafT) -> Self {
+        Self {
+            max: value.0,
+            pos: value.1,
+            delta: 0,
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use super::segment_tree::SegmentTreeState;
+use core::cmp;
+
+// Sums of non-empty subarrays; the identity holds i64::MIN for every bound, which is why
+// combining uses saturating additions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaxSubarrayValue {
+    pub sum: i64,
+    pub prefix: i64,
+    pub suffix: i64,
+    pub best: i64,
+}
+
+impl MaxSubarrayValue {
+    fn uniform(element: i64, len: usize) -> Self {
+        let sum = element * (len as i64);
+        let bound = if element > 0 { sum } else { element };
+        Self {
+            sum,
+            prefix: bound,
+            suffix: bound,
+            best: bound,
+        }
+    }
+}
+
+// Maximum subarray sum with range assign, the delta is the pending assignment if any
+#[derive(Debug, Clone)]
+pub struct MaxSubarrayNode {
+    value: MaxSubarrayValue,
+    delta: Option<i64>,
+}
+
+impl SegmentTreeState for MaxSubarrayNode {
+    type ArrayT = i64;
+    type LeafT = MaxSubarrayValue;
+    type DeltaT = Option<i64>;
+
+    fn reduce(a: Self::LeafT, b: Self::LeafT) -> Self::LeafT {
+        MaxSubarrayValue {
+            sum: a.sum + b.sum,
+            prefix: cmp::max(a.prefix, a.sum.saturating_add(b.prefix)),
+            suffix: cmp::max(b.suffix, b.sum.saturating_add(a.suffix)),
+            best: cmp::max(cmp::max(a.best, b.best), a.suffix.saturating_add(b.prefix)),
+        }
+    }
+
+    fn identity() -> Self::LeafT {
+        MaxSubarrayValue {
+            sum: 0,
+            prefix: i64::MIN,
+            suffix: i64::MIN,
+            best: i64::MIN,
+        }
+    }
+
+    fn compose_delta(delta: &Self::DeltaT, next_delta: &Self::DeltaT) -> Self::DeltaT {
+        next_delta.or(*delta)
+    }
+
+    fn apply_delta_to_value(value: Self::LeafT, delta: &Self::DeltaT, len: usize) -> Self::LeafT {
+        match delta {
+            Some(element) => MaxSubarrayValue::uniform(*element, len),
+            None => value,
+        }
+    }
+
+    fn reverse_value(value: Self::LeafT) -> Self::LeafT {
+        MaxSubarrayValue {
+            prefix: value.suffix,
+            suffix: value.prefix,
+            ..value
+        }
+    }
+
+    fn new() -> Self {
+        Self {
+            value: Self::identity(),
+            delta: None,
+        }
+    }
+
+    fn new_leaf(element: &Self::ArrayT, _leaf_pos: usize) -> Self {
+        Self::new_range(element, 0, 0)
+    }
+
+    fn new_range(element: &Self::ArrayT, left: usize, right: usize) -> Self {
+        Self {
+            value: MaxSubarrayValue::uniform(*element, right - left + 1),
+            delta: None,
+        }
+    }
+
+    fn new_with_children(left_child: &Self, right_child: &Self) -> Self {
+        Self {
+            value: Self::reduce(left_child.value, right_child.value),
+            delta: None,
+        }
+    }
+
+    fn get_delta(&self) -> Self::DeltaT {
+        self.delta
+    }
+
+    fn update_delta(&mut self, delta: &Self::DeltaT) {
+        self.delta = Self::compose_delta(&self.delta, delta);
+    }
+
+    fn get_value(&self) -> Self::LeafT {
+        self.value
+    }
+
+    fn update_value(&mut self, left_child: &Self, right_child: &Self) {
+        self.value = Self::reduce(left_child.value, right_child.value);
+    }
+
+    fn push(&mut self, left_child: &mut Self, right_child: &mut Self, len: usize) {
+        left_child.update_delta(&self.delta);
+        right_child.update_delta(&self.delta);
+        self.value = Self::apply_delta_to_value(self.value, &self.delta, len);
+        self.delta = None;
+    }
+
+    fn push_leaf(&mut self) {
+        self.value = Self::apply_delta_to_value(self.value, &self.delta, 1);
+        self.delta = None;
+    }
+}
@@ -0,0 +1,106 @@
+use super::segment_tree::CommutativeDelta;
+use super::segment_tree::MergeableLeaf;
+use super::segment_tree::SegmentTreeState;
+
+// Range minimum with range add; the value is (minimum, leftmost position of the minimum)
+#[derive(Debug, Clone)]
+pub struct MinNode {
+    min: i64,
+    pos: usize,
+    delta: i64,
+}
+
+impl SegmentTreeState for MinNode {
+    type ArrayT = i64;
+    type LeafT = (i64, usize);
+    type DeltaT = i64;
+
+    fn reduce(a: Self::LeafT, b: Self::LeafT) -> Self::LeafT {
+        if b.0 < a.0 || (b.0 == a.0 && b.1 < a.1) {
+            b
+        } else {
+            a
+        }
+    }
+
+    fn identity() -> Self::LeafT {
+        (i64::MAX, usize::MAX)
+    }
+
+    fn compose_delta(delta: &Self::DeltaT, next_delta: &Self::DeltaT) -> Self::DeltaT {
+        delta + next_delta
+    }
+
+    fn apply_delta_to_value(value: Self::LeafT, delta: &Self::DeltaT, _len: usize) -> Self::LeafT {
+        (value.0 + delta, value.1)
+    }
+
+    fn new() -> Self {
+        Self {
+            min: i64::MAX,
+            pos: usize::MAX,
+            delta: 0,
+        }
+    }
+
+    fn new_leaf(element: &Self::ArrayT, leaf_pos: usize) -> Self {
+        Self {
+            min: *element,
+            pos: leaf_pos,
+            delta: 0,
+        }
+    }
+
+    fn new_range(element: &Self::ArrayT, left: usize, _right: usize) -> Self {
+        Self {
+            min: *element,
+            pos: left,
+            delta: 0,
+        }
+    }
+
+    fn new_with_children(left_child: &Self, right_child: &Self) -> Self {
+        let (min, pos) = Self::reduce(left_child.get_value(), right_child.get_value());
+        Self { min, pos, delta: 0 }
+    }
+
+    fn get_delta(&self) -> Self::DeltaT {
+        self.delta
+    }
+
+    fn update_delta(&mut self, delta: &Self::DeltaT) {
+        self.delta += delta;
+    }
+
+    fn get_value(&self) -> Self::LeafT {
+        (self.min, self.pos)
+    }
+
+    fn update_value(&mut self, left_child: &Self, right_child: &Self) {
+        (self.min, self.pos) = Self::reduce(left_child.get_value(), right_child.get_value());
+    }
+
+    fn push(&mut self, left_child: &mut Self, right_child: &mut Self, _len: usize) {
+        self.min += self.delta;
+        left_child.delta += self.delta;
+        right_child.delta += self.delta;
+        self.delta = 0;
+    }
+
+    fn push_leaf(&mut self) {
+        self.min += self.delta;
+        self.delta = 0;
+    }
+}
+
+impl CommutativeDelta for MinNode {}
+
+impl MergeableLeaf for MinNode {
+    fn new_leaf_from_value(value: Self::LeafT) -> Self {
+        Self {
+            min: value.0,
+            pos: value.1,
+            delta: 0,
+        }
+    }
+}
@@ -1,8 +1,52 @@
+pub mod affine_node;
+pub mod and_node;
+pub mod assign_node;
+pub mod beats_node;
+pub mod checked;
+pub mod compressed_segment_tree;
+pub mod error;
+pub mod gcd_node;
+pub mod max_node;
+pub mod max_subarray_node;
+pub mod min_node;
+#[allow(clippy::module_inception)]
 pub mod segment_tree;
+pub mod segment_tree_2d;
+pub mod segment_tree_bottom_up;
 pub mod segment_tree_bumped;
+pub mod segment_tree_fn;
+pub mod segment_tree_persistent;
+pub mod segment_tree_sparse;
 pub mod sum_node;
+pub mod sum_value;
 
+pub use affine_node::AffineDelta;
+pub use affine_node::AffineNode;
+pub use and_node::AndNode;
+pub use assign_node::AssignDelta;
+pub use assign_node::AssignNode;
+pub use assign_node::AssignValue;
+pub use beats_node::BeatsDelta;
+pub use beats_node::BeatsNode;
+pub use beats_node::BeatsValue;
+pub use checked::Checked;
+pub use compressed_segment_tree::CompressedSegmentTree;
+pub use error::SegmentTreeError;
+pub use gcd_node::GcdNode;
+pub use max_node::MaxNode;
+pub use max_subarray_node::MaxSubarrayNode;
+pub use max_subarray_node::MaxSubarrayValue;
+pub use min_node::MinNode;
+pub use segment_tree::AdditiveDelta;
+pub use segment_tree::CommutativeDelta;
+pub use segment_tree::MergeableLeaf;
 pub use segment_tree::SegmentTree;
 pub use segment_tree::SegmentTreeState;
+pub use segment_tree_2d::SegmentTree2D;
+pub use segment_tree_bottom_up::SegmentTreeBottomUp;
 pub use segment_tree_bumped::SegmentTreeBmp;
+pub use segment_tree_fn::SegmentTreeFn;
+pub use segment_tree_persistent::SegmentTreePersistent;
+pub use segment_tree_sparse::SegmentTreeSparse;
 pub use sum_node::SumNode;
+pub use sum_value::SumValue;
@@ -1,39 +1,64 @@
+use super::error::check_index;
+use super::error::check_range;
+use super::error::SegmentTreeError;
 use core::cmp;
-use std::fmt::Display;
 
 pub trait SegmentTreeState: Clone + Sized {
-    type LeafT: Default + Copy + Display;
-    type DeltaT;
-    fn merge(a: Self::LeafT, b: Self::LeafT) -> Self::LeafT;
-    fn new() -> Self;
-    fn new_with_leaf(leaf_value: Self::LeafT, leaf_pos: usize) -> Self;
-    fn new_with_children(left_child: &Self, right_child: &Self) -> Self;
-    fn get_delta(&self) -> Self::DeltaT;
-    fn update_delta(&mut self, delta: &Self::DeltaT);
-    fn get_value(&self) -> Self::LeafT;
-    fn push(&mut self, left_child: &mut Self, right_child: &mut Self);
-    fn push_leaf(&mut self);
-    fn compute(&mut self, left_child: &Self, right_child: &Self);
-}
-
-// TODO: use the better trait definition below
-/*
-pub trait SegmentTreeState: Clone + Sized {
-    type ArrayT: Default + Display;
-    type LeafT: Default + Display;
-    type DeltaT;
+    type ArrayT;
+    type LeafT: Clone;
+    type DeltaT: Clone;
     fn reduce(a: Self::LeafT, b: Self::LeafT) -> Self::LeafT;
+    fn identity() -> Self::LeafT;
+    fn compose_delta(delta: &Self::DeltaT, next_delta: &Self::DeltaT) -> Self::DeltaT;
+    fn apply_delta_to_value(value: Self::LeafT, delta: &Self::DeltaT, len: usize) -> Self::LeafT;
+    // Value of the same range read back to front, used by Treap::reverse. The default is only
+    // right when `reduce` is commutative.
+    fn reverse_value(value: Self::LeafT) -> Self::LeafT {
+        value
+    }
     fn new() -> Self;
-    fn new_leaf(element: Self::ArrayT, leaf_pos: usize) -> Self;
+    fn new_leaf(element: &Self::ArrayT, leaf_pos: usize) -> Self;
+    // State of [left, right] with every position holding `element`. The default builds the
+    // whole subtree, nodes used with SegmentTreeSparse should override it with an O(1) version.
+    fn new_range(element: &Self::ArrayT, left: usize, right: usize) -> Self {
+        if left == right {
+            return Self::new_leaf(element, left);
+        }
+        let middle = left + (right - left) / 2;
+        Self::new_with_children(
+            &Self::new_range(element, left, middle),
+            &Self::new_range(element, middle + 1, right),
+        )
+    }
     fn new_with_children(left_child: &Self, right_child: &Self) -> Self;
     fn get_delta(&self) -> Self::DeltaT;
     fn update_delta(&mut self, delta: &Self::DeltaT);
+    // Called on fully covered inner nodes, returning false makes the tree descend further
+    // instead of tagging the node (the break/tag condition of segment tree beats)
+    fn can_absorb(&self, _delta: &Self::DeltaT) -> bool {
+        true
+    }
     fn get_value(&self) -> Self::LeafT;
     fn update_value(&mut self, left_child: &Self, right_child: &Self);
-    fn push(&mut self, left_child: &mut Self, right_child: &mut Self);
+    // `len` is the number of positions covered by the node, so nodes need not store it
+    fn push(&mut self, left_child: &mut Self, right_child: &mut Self, len: usize);
     fn push_leaf(&mut self);
 }
- */
+
+// Marker for nodes whose deltas commute (`compose_delta(a, b) == compose_delta(b, a)` and
+// applying them in any order gives the same value), required by SegmentTreePersistent
+pub trait CommutativeDelta: SegmentTreeState {}
+
+// Marker for nodes where applying a delta to each of k positions and reducing them gives the
+// same value as applying the delta composed with itself k times to their reduced value (sums,
+// but not minima), required by SegmentTree2D::update
+pub trait AdditiveDelta: CommutativeDelta {}
+
+// Nodes where the `reduce` of two values of the same position is again the value of a single
+// position (sums, minima, gcds, ...), required by SegmentTreeSparse::merge to combine leaves
+pub trait MergeableLeaf: SegmentTreeState {
+    fn new_leaf_from_value(value: Self::LeafT) -> Self;
+}
 
 pub struct SegmentTree<NodeT: SegmentTreeState> {
     node_state: NodeT,
@@ -44,10 +69,10 @@ pub struct SegmentTree<NodeT: SegmentTreeState> {
 }
 
 impl<NodeT: SegmentTreeState> SegmentTree<NodeT> {
-    fn _new(arr: &Vec<NodeT::LeafT>, left: usize, right: usize) -> Self {
+    fn _new(arr: &[NodeT::ArrayT], left: usize, right: usize) -> Self {
         if left == right {
             return Self {
-                node_state: NodeT::new_with_leaf(arr[left], left),
+                node_state: NodeT::new_leaf(&arr[left], left),
                 left_node: None,
                 right_node: None,
                 range_left: left,
@@ -57,17 +82,33 @@ impl<NodeT: SegmentTreeState> SegmentTree<NodeT> {
         let middle = (left + right) / 2;
         let left_node = Self::_new(arr, left, middle);
         let right_node = Self::_new(arr, middle + 1, right);
-        return Self {
+        Self {
             node_state: NodeT::new_with_children(&left_node.node_state, &right_node.node_state),
             left_node: Some(Box::new(left_node)),
             right_node: Some(Box::new(right_node)),
             range_left: left,
             range_right: right,
-        };
+        }
     }
 
-    pub fn new(arr: &Vec<NodeT::LeafT>) -> Self {
-        Self::_new(arr, 0, ((arr.len() as i32) - 1) as usize)
+    // Panics on an empty array, see try_new
+    pub fn new(arr: &[NodeT::ArrayT]) -> Self {
+        Self::try_new(arr).unwrap()
+    }
+
+    pub fn try_new(arr: &[NodeT::ArrayT]) -> Result<Self, SegmentTreeError> {
+        if arr.is_empty() {
+            return Err(SegmentTreeError::EmptyInput);
+        }
+        Ok(Self::_new(arr, 0, arr.len() - 1))
+    }
+
+    pub fn len(&self) -> usize {
+        self.range_right - self.range_left + 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
     }
 
     fn _push_node(&mut self) {
@@ -75,8 +116,9 @@ impl<NodeT: SegmentTreeState> SegmentTree<NodeT> {
             self.node_state.push_leaf();
         } else {
             self.node_state.push(
-                &mut (*self.left_node.as_mut().unwrap()).node_state,
-                &mut (*self.right_node.as_mut().unwrap()).node_state,
+                &mut self.left_node.as_mut().unwrap().node_state,
+                &mut self.right_node.as_mut().unwrap().node_state,
+                self.range_right - self.range_left + 1,
             );
         }
     }
@@ -89,38 +131,236 @@ impl<NodeT: SegmentTreeState> SegmentTree<NodeT> {
             return;
         }
 
-        if left == self.range_left && right == self.range_right {
+        if left == self.range_left
+            && right == self.range_right
+            && (left == right || self.node_state.can_absorb(delta))
+        {
             self.node_state.update_delta(delta);
             self._push_node();
             return;
         }
 
         self._push_node();
-        (*self.left_node.as_mut().unwrap()).update(left, right, delta);
-        (*self.right_node.as_mut().unwrap()).update(left, right, delta);
+        self.left_node.as_mut().unwrap().update(left, right, delta);
+        self.right_node.as_mut().unwrap().update(left, right, delta);
 
-        self.node_state.compute(
-            &(*self.left_node.as_ref().unwrap()).node_state,
-            &(*self.right_node.as_ref().unwrap()).node_state,
+        self.node_state.update_value(
+            &self.left_node.as_ref().unwrap().node_state,
+            &self.right_node.as_ref().unwrap().node_state,
         );
     }
 
-    pub fn query(&mut self, left: usize, right: usize) -> NodeT::LeafT {
+    pub fn try_update(
+        &mut self,
+        left: usize,
+        right: usize,
+        delta: &NodeT::DeltaT,
+    ) -> Result<(), SegmentTreeError> {
+        check_range(left, right, self.len())?;
+        self.update(left, right, delta);
+        Ok(())
+    }
+
+    // Pending deltas are applied to the partial results on the way up instead of being pushed
+    pub fn query(&self, left: usize, right: usize) -> NodeT::LeafT {
         let left = cmp::max(left, self.range_left);
         let right = cmp::min(right, self.range_right);
 
         if left > right {
-            return NodeT::LeafT::default();
+            return NodeT::identity();
         }
 
+        let value = if left == self.range_left && right == self.range_right {
+            self.node_state.get_value()
+        } else {
+            NodeT::reduce(
+                self.left_node.as_ref().unwrap().query(left, right),
+                self.right_node.as_ref().unwrap().query(left, right),
+            )
+        };
+        NodeT::apply_delta_to_value(value, &self.node_state.get_delta(), right - left + 1)
+    }
+
+    pub fn try_query(&self, left: usize, right: usize) -> Result<NodeT::LeafT, SegmentTreeError> {
+        check_range(left, right, self.len())?;
+        Ok(self.query(left, right))
+    }
+
+    fn _get(&mut self, pos: usize) -> NodeT::LeafT {
         self._push_node();
-        if left == self.range_left && right == self.range_right {
+        if self.range_left == self.range_right {
             return self.node_state.get_value();
         }
 
-        return NodeT::merge(
-            (*self.left_node.as_mut().unwrap()).query(left, right),
-            (*self.right_node.as_mut().unwrap()).query(left, right),
-        );
+        if pos <= self.left_node.as_ref().unwrap().range_right {
+            self.left_node.as_mut().unwrap()._get(pos)
+        } else {
+            self.right_node.as_mut().unwrap()._get(pos)
+        }
+    }
+
+    // Panics if pos is out of bounds, see try_get
+    pub fn get(&mut self, pos: usize) -> NodeT::LeafT {
+        self.try_get(pos).unwrap()
+    }
+
+    pub fn try_get(&mut self, pos: usize) -> Result<NodeT::LeafT, SegmentTreeError> {
+        check_index(pos, self.len())?;
+        Ok(self._get(pos))
+    }
+
+    fn _set(&mut self, pos: usize, element: &NodeT::ArrayT) {
+        self._push_node();
+        if self.range_left == self.range_right {
+            self.node_state = NodeT::new_leaf(element, pos);
+            return;
+        }
+
+        let left_node = self.left_node.as_mut().unwrap();
+        let right_node = self.right_node.as_mut().unwrap();
+        if pos <= left_node.range_right {
+            left_node._set(pos, element);
+            right_node._push_node();
+        } else {
+            left_node._push_node();
+            right_node._set(pos, element);
+        }
+
+        self.node_state
+            .update_value(&left_node.node_state, &right_node.node_state);
+    }
+
+    // Panics if pos is out of bounds, see try_set
+    pub fn set(&mut self, pos: usize, element: &NodeT::ArrayT) {
+        self.try_set(pos, element).unwrap()
+    }
+
+    pub fn try_set(&mut self, pos: usize, element: &NodeT::ArrayT) -> Result<(), SegmentTreeError> {
+        check_index(pos, self.len())?;
+        self._set(pos, element);
+        Ok(())
+    }
+
+    fn _collect(&mut self, values: &mut Vec<NodeT::LeafT>) {
+        self._push_node();
+        if self.range_left == self.range_right {
+            values.push(self.node_state.get_value());
+            return;
+        }
+
+        self.left_node.as_mut().unwrap()._collect(values);
+        self.right_node.as_mut().unwrap()._collect(values);
+    }
+
+    // Pushes every pending delta down to the leaves and returns the leaf values in order
+    pub fn to_vec(&mut self) -> Vec<NodeT::LeafT> {
+        let mut values = Vec::with_capacity(self.range_right - self.range_left + 1);
+        self._collect(&mut values);
+        values
+    }
+
+    // Returns the first position >= left where pred stops holding for acc + prefix
+    fn _max_right<F: Fn(&NodeT::LeafT) -> bool>(
+        &mut self,
+        left: usize,
+        pred: &F,
+        acc: &mut NodeT::LeafT,
+    ) -> Option<usize> {
+        if self.range_right < left {
+            return None;
+        }
+
+        self._push_node();
+        if left <= self.range_left {
+            let value = NodeT::reduce(acc.clone(), self.node_state.get_value());
+            if pred(&value) {
+                *acc = value;
+                return None;
+            }
+            if self.range_left == self.range_right {
+                return Some(self.range_left);
+            }
+        }
+
+        match self.left_node.as_mut().unwrap()._max_right(left, pred, acc) {
+            Some(pos) => Some(pos),
+            None => self
+                .right_node
+                .as_mut()
+                .unwrap()
+                ._max_right(left, pred, acc),
+        }
+    }
+
+    // Largest `right` with pred(query(left, right)), None if pred already fails on [left, left]
+    // or `left` is past the end.
+    // pred must be monotone (once false it stays false as the range grows) and hold for identity.
+    pub fn max_right<F: Fn(&NodeT::LeafT) -> bool>(
+        &mut self,
+        left: usize,
+        pred: F,
+    ) -> Option<usize> {
+        if left > self.range_right {
+            return None;
+        }
+        let mut acc = NodeT::identity();
+        match self._max_right(left, &pred, &mut acc) {
+            Some(pos) if pos == left => None,
+            Some(pos) => Some(pos - 1),
+            None => Some(self.range_right),
+        }
+    }
+
+    // Returns the last position <= right where pred stops holding for suffix + acc
+    fn _min_left<F: Fn(&NodeT::LeafT) -> bool>(
+        &mut self,
+        right: usize,
+        pred: &F,
+        acc: &mut NodeT::LeafT,
+    ) -> Option<usize> {
+        if self.range_left > right {
+            return None;
+        }
+
+        self._push_node();
+        if self.range_right <= right {
+            let value = NodeT::reduce(self.node_state.get_value(), acc.clone());
+            if pred(&value) {
+                *acc = value;
+                return None;
+            }
+            if self.range_left == self.range_right {
+                return Some(self.range_left);
+            }
+        }
+
+        match self
+            .right_node
+            .as_mut()
+            .unwrap()
+            ._min_left(right, pred, acc)
+        {
+            Some(pos) => Some(pos),
+            None => self.left_node.as_mut().unwrap()._min_left(right, pred, acc),
+        }
+    }
+
+    // Smallest `left` with pred(query(left, right)), None if pred already fails on [right, right]
+    // or `right` is past the end.
+    // pred must be monotone (once false it stays false as the range grows) and hold for identity.
+    pub fn min_left<F: Fn(&NodeT::LeafT) -> bool>(
+        &mut self,
+        right: usize,
+        pred: F,
+    ) -> Option<usize> {
+        if right > self.range_right {
+            return None;
+        }
+        let mut acc = NodeT::identity();
+        match self._min_left(right, &pred, &mut acc) {
+            Some(pos) if pos == right => None,
+            Some(pos) => Some(pos + 1),
+            None => Some(self.range_left),
+        }
     }
 }
@@ -0,0 +1,267 @@
+use super::error::SegmentTreeError;
+use super::segment_tree::AdditiveDelta;
+use super::segment_tree::SegmentTreeState;
+use super::segment_tree_bumped::SegmentTreeBmp;
+use core::cmp;
+
+// Tree of trees: the outer tree splits the rows, and every outer node keeps a SegmentTreeBmp
+// over the columns whose leaves hold the aggregate of its rows (stacked with
+// `new_with_children`). Results and `LeafT` positions therefore refer to columns.
+pub struct SegmentTree2D<NodeT: SegmentTreeState> {
+    trees: Vec<Option<SegmentTreeBmp<NodeT>>>,
+    rows: usize,
+    cols: usize,
+}
+
+impl<NodeT: SegmentTreeState> SegmentTree2D<NodeT> {
+    fn _populate(
+        grid: &[Vec<NodeT::ArrayT>],
+        trees: &mut Vec<Option<SegmentTreeBmp<NodeT>>>,
+        node_id: usize,
+        top: usize,
+        bottom: usize,
+    ) {
+        if top == bottom {
+            trees[node_id] = Some(SegmentTreeBmp::new(&grid[top]));
+            return;
+        }
+        let middle = (top + bottom) / 2;
+        Self::_populate(grid, trees, node_id * 2 + 1, top, middle);
+        Self::_populate(grid, trees, node_id * 2 + 2, middle + 1, bottom);
+        trees[node_id] = Some(SegmentTreeBmp::new_stacked(
+            trees[node_id * 2 + 1].as_ref().unwrap(),
+            trees[node_id * 2 + 2].as_ref().unwrap(),
+        ));
+    }
+
+    // Panics on an empty or ragged grid, see try_new
+    pub fn new(grid: &[Vec<NodeT::ArrayT>]) -> Self {
+        Self::try_new(grid).unwrap()
+    }
+
+    // Every row of the grid must have the same, non-zero length
+    pub fn try_new(grid: &[Vec<NodeT::ArrayT>]) -> Result<Self, SegmentTreeError> {
+        if grid.is_empty() || grid[0].is_empty() {
+            return Err(SegmentTreeError::EmptyInput);
+        }
+        let cols = grid[0].len();
+        if let Some(row) = grid.iter().position(|values| values.len() != cols) {
+            return Err(SegmentTreeError::RaggedGrid {
+                row,
+                len: grid[row].len(),
+                expected: cols,
+            });
+        }
+
+        let mut trees = Vec::new();
+        trees.resize_with(grid.len() * 4, || None);
+        Self::_populate(grid, &mut trees, 0, 0, grid.len() - 1);
+        Ok(Self {
+            trees,
+            rows: grid.len(),
+            cols,
+        })
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    fn _tree(&mut self, node_id: usize) -> &mut SegmentTreeBmp<NodeT> {
+        self.trees[node_id].as_mut().unwrap()
+    }
+
+    // Rebuilds the stacked leaf of `col` on the path from the root to the leaf of `row`, after
+    // `change` has been applied to the tree of that leaf
+    fn _update_column<F: Fn(&mut SegmentTreeBmp<NodeT>)>(
+        &mut self,
+        node_id: usize,
+        top: usize,
+        bottom: usize,
+        row: usize,
+        col: usize,
+        change: &F,
+    ) {
+        if top == bottom {
+            change(self._tree(node_id));
+            return;
+        }
+
+        let middle = (top + bottom) / 2;
+
+        if row <= middle {
+            self._update_column(node_id * 2 + 1, top, middle, row, col, change);
+        } else {
+            self._update_column(node_id * 2 + 2, middle + 1, bottom, row, col, change);
+        }
+        let left_state = self._tree(node_id * 2 + 1).get_leaf_state(col);
+        let right_state = self._tree(node_id * 2 + 2).get_leaf_state(col);
+        self._tree(node_id)
+            .set_leaf_state(col, NodeT::new_with_children(&left_state, &right_state));
+    }
+
+    // Applies `delta` to a single cell, valid for any delta
+    pub fn update_point(&mut self, row: usize, col: usize, delta: &NodeT::DeltaT) {
+        self._update_column(0, 0, self.rows - 1, row, col, &|tree| {
+            tree.update(col, col, delta)
+        });
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, element: &NodeT::ArrayT) {
+        self._update_column(0, 0, self.rows - 1, row, col, &|tree| {
+            tree.set(col, element)
+        });
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn _query_subtree(
+        &self,
+        node_id: usize,
+        top: usize,
+        bottom: usize,
+        top_query: usize,
+        bottom_query: usize,
+        left: usize,
+        right: usize,
+    ) -> NodeT::LeafT {
+        if top_query > bottom_query {
+            return NodeT::identity();
+        }
+
+        if top_query == top && bottom_query == bottom {
+            return self.trees[node_id].as_ref().unwrap().query(left, right);
+        }
+
+        let middle = (top + bottom) / 2;
+
+        NodeT::reduce(
+            self._query_subtree(
+                node_id * 2 + 1,
+                top,
+                middle,
+                top_query,
+                cmp::min(bottom_query, middle),
+                left,
+                right,
+            ),
+            self._query_subtree(
+                node_id * 2 + 2,
+                middle + 1,
+                bottom,
+                cmp::max(top_query, middle + 1),
+                bottom_query,
+                left,
+                right,
+            ),
+        )
+    }
+
+    pub fn query(&self, row1: usize, col1: usize, row2: usize, col2: usize) -> NodeT::LeafT {
+        let col2 = cmp::min(col2, self.cols - 1);
+        if col1 > col2 {
+            return NodeT::identity();
+        }
+        self._query_subtree(
+            0,
+            0,
+            self.rows - 1,
+            row1,
+            cmp::min(row2, self.rows - 1),
+            col1,
+            col2,
+        )
+    }
+}
+
+impl<NodeT: AdditiveDelta> SegmentTree2D<NodeT> {
+    // `delta` composed with itself `times` times, by repeated doubling
+    fn _repeat_delta(delta: &NodeT::DeltaT, times: usize) -> NodeT::DeltaT {
+        let mut result: Option<NodeT::DeltaT> = None;
+        let mut power = delta.clone();
+        let mut times = times;
+        while times > 0 {
+            if times & 1 == 1 {
+                result = Some(match result {
+                    Some(result) => NodeT::compose_delta(&result, &power),
+                    None => power.clone(),
+                });
+            }
+            power = NodeT::compose_delta(&power, &power);
+            times >>= 1;
+        }
+        result.unwrap()
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn _update_subtree(
+        &mut self,
+        delta: &NodeT::DeltaT,
+        node_id: usize,
+        top: usize,
+        bottom: usize,
+        top_update: usize,
+        bottom_update: usize,
+        left: usize,
+        right: usize,
+    ) {
+        if top_update > bottom_update {
+            return;
+        }
+
+        let repeated = Self::_repeat_delta(delta, bottom_update - top_update + 1);
+        self._tree(node_id).update(left, right, &repeated);
+        if top == bottom {
+            return;
+        }
+
+        let middle = (top + bottom) / 2;
+
+        self._update_subtree(
+            delta,
+            node_id * 2 + 1,
+            top,
+            middle,
+            top_update,
+            cmp::min(bottom_update, middle),
+            left,
+            right,
+        );
+        self._update_subtree(
+            delta,
+            node_id * 2 + 2,
+            middle + 1,
+            bottom,
+            cmp::max(top_update, middle + 1),
+            bottom_update,
+            left,
+            right,
+        );
+    }
+
+    // Applies `delta` to every cell of the rectangle. Each outer node sees the delta once per
+    // covered row of its own, composed into a single tag, hence the AdditiveDelta bound. Costs
+    // O((row2 - row1 + log rows) * log cols); use `update_point` for other deltas.
+    pub fn update(
+        &mut self,
+        row1: usize,
+        col1: usize,
+        row2: usize,
+        col2: usize,
+        delta: &NodeT::DeltaT,
+    ) {
+        self._update_subtree(
+            delta,
+            0,
+            0,
+            self.rows - 1,
+            row1,
+            cmp::min(row2, self.rows - 1),
+            col1,
+            cmp::min(col2, self.cols - 1),
+        );
+    }
+}
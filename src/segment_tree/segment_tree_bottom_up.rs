@@ -0,0 +1,163 @@
+use super::error::SegmentTreeError;
+use super::segment_tree::SegmentTreeState;
+
+// Iterative layout: node k has children 2k and 2k + 1, leaf i lives at `size + i` and the
+// positions past the array end are padded with `NodeT::new()`
+pub struct SegmentTreeBottomUp<NodeT: SegmentTreeState> {
+    nodes: Vec<NodeT>,
+    arr_size: usize,
+    size: usize,
+    log: usize,
+}
+
+impl<NodeT: SegmentTreeState> SegmentTreeBottomUp<NodeT> {
+    // Panics on an empty array, see try_new
+    pub fn new(arr: &[NodeT::ArrayT]) -> Self {
+        Self::try_new(arr).unwrap()
+    }
+
+    pub fn try_new(arr: &[NodeT::ArrayT]) -> Result<Self, SegmentTreeError> {
+        if arr.is_empty() {
+            return Err(SegmentTreeError::EmptyInput);
+        }
+        let mut log = 0;
+        while (1 << log) < arr.len() {
+            log += 1;
+        }
+        let size = 1 << log;
+
+        let mut nodes = vec![NodeT::new(); 2 * size];
+        for (pos, element) in arr.iter().enumerate() {
+            nodes[size + pos] = NodeT::new_leaf(element, pos);
+        }
+        for node_id in (1..size).rev() {
+            nodes[node_id] = NodeT::new_with_children(&nodes[2 * node_id], &nodes[2 * node_id + 1]);
+        }
+
+        Ok(Self {
+            nodes,
+            arr_size: arr.len(),
+            size,
+            log,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.arr_size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.arr_size == 0
+    }
+
+    // Number of leaves below node k, padding included
+    fn _node_len(&self, node_id: usize) -> usize {
+        self.size >> (usize::BITS - 1 - node_id.leading_zeros())
+    }
+
+    fn _push_node(&mut self, node_id: usize) {
+        if node_id >= self.size {
+            self.nodes[node_id].push_leaf();
+        } else {
+            let len = self._node_len(node_id);
+            let (root_portion, rest) = self.nodes.split_at_mut(2 * node_id);
+            let (l_nodes, r_nodes) = rest.split_at_mut(1);
+            root_portion[node_id].push(&mut l_nodes[0], &mut r_nodes[0], len);
+        }
+    }
+
+    fn _compute_node(&mut self, node_id: usize) {
+        self._push_node(2 * node_id);
+        self._push_node(2 * node_id + 1);
+        let (root_portion, rest) = self.nodes.split_at_mut(2 * node_id);
+        root_portion[node_id].update_value(&rest[0], &rest[1]);
+    }
+
+    fn _apply_node(&mut self, node_id: usize, delta: &NodeT::DeltaT) {
+        if node_id >= self.size || self.nodes[node_id].can_absorb(delta) {
+            self.nodes[node_id].update_delta(delta);
+            self._push_node(node_id);
+            return;
+        }
+
+        self._push_node(node_id);
+        self._apply_node(2 * node_id, delta);
+        self._apply_node(2 * node_id + 1, delta);
+        self._compute_node(node_id);
+    }
+
+    // Pushes the pending deltas on the paths from the root to both boundaries of [left, right)
+    fn _push_boundaries(&mut self, left: usize, right: usize) {
+        for i in (1..=self.log).rev() {
+            if ((left >> i) << i) != left {
+                self._push_node(left >> i);
+            }
+            if ((right >> i) << i) != right {
+                self._push_node((right - 1) >> i);
+            }
+        }
+    }
+
+    pub fn update(&mut self, left: usize, right: usize, delta: &NodeT::DeltaT) {
+        let right = right.min(self.arr_size - 1);
+        if left > right {
+            return;
+        }
+        let left = left + self.size;
+        let right = right + 1 + self.size;
+        self._push_boundaries(left, right);
+
+        let (mut l, mut r) = (left, right);
+        while l < r {
+            if l & 1 == 1 {
+                self._apply_node(l, delta);
+                l += 1;
+            }
+            if r & 1 == 1 {
+                r -= 1;
+                self._apply_node(r, delta);
+            }
+            l >>= 1;
+            r >>= 1;
+        }
+
+        for i in 1..=self.log {
+            if ((left >> i) << i) != left {
+                self._compute_node(left >> i);
+            }
+            if ((right >> i) << i) != right {
+                self._compute_node((right - 1) >> i);
+            }
+        }
+    }
+
+    pub fn query(&mut self, left: usize, right: usize) -> NodeT::LeafT {
+        let right = right.min(self.arr_size - 1);
+        if left > right {
+            return NodeT::identity();
+        }
+        let left = left + self.size;
+        let right = right + 1 + self.size;
+        self._push_boundaries(left, right);
+
+        let mut left_value = NodeT::identity();
+        let mut right_value = NodeT::identity();
+        let (mut l, mut r) = (left, right);
+        while l < r {
+            if l & 1 == 1 {
+                self._push_node(l);
+                left_value = NodeT::reduce(left_value, self.nodes[l].get_value());
+                l += 1;
+            }
+            if r & 1 == 1 {
+                r -= 1;
+                self._push_node(r);
+                right_value = NodeT::reduce(self.nodes[r].get_value(), right_value);
+            }
+            l >>= 1;
+            r >>= 1;
+        }
+
+        NodeT::reduce(left_value, right_value)
+    }
+}
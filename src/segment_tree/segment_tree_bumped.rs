@@ -1,21 +1,27 @@
+use super::error::check_index;
+use super::error::check_range;
+use super::error::SegmentTreeError;
 use super::segment_tree::SegmentTreeState;
 use core::cmp;
 
 pub struct SegmentTreeBmp<NodeT: SegmentTreeState> {
     nodes: Vec<NodeT>,
     arr_size: usize,
+    // Previous states of every node modified since the first live checkpoint, None while
+    // there is no checkpoint to roll back to
+    journal: Option<Vec<(usize, NodeT)>>,
 }
 
 impl<NodeT: SegmentTreeState> SegmentTreeBmp<NodeT> {
     fn _populate(
-        arr: &Vec<NodeT::LeafT>,
+        arr: &[NodeT::ArrayT],
         nodes: &mut Vec<NodeT>,
         node_id: usize,
         left: usize,
         right: usize,
     ) {
         if left == right {
-            nodes[node_id] = NodeT::new_with_leaf(arr[left], left);
+            nodes[node_id] = NodeT::new_leaf(&arr[left], left);
             return;
         }
         let middle = (left + right) / 2;
@@ -24,24 +30,93 @@ impl<NodeT: SegmentTreeState> SegmentTreeBmp<NodeT> {
         nodes[node_id] = NodeT::new_with_children(&nodes[node_id * 2 + 1], &nodes[node_id * 2 + 2]);
     }
 
-    pub fn new(arr: &Vec<NodeT::LeafT>) -> Self {
+    // Panics on an empty array, see try_new
+    pub fn new(arr: &[NodeT::ArrayT]) -> Self {
+        Self::try_new(arr).unwrap()
+    }
+
+    pub fn try_new(arr: &[NodeT::ArrayT]) -> Result<Self, SegmentTreeError> {
+        if arr.is_empty() {
+            return Err(SegmentTreeError::EmptyInput);
+        }
         let mut nodes = vec![NodeT::new(); arr.len() * 5];
-        Self::_populate(arr, &mut nodes, 0, 0, ((arr.len() as i32) - 1) as usize);
-        Self {
+        Self::_populate(arr, &mut nodes, 0, 0, arr.len() - 1);
+        Ok(Self {
             nodes,
             arr_size: arr.len(),
+            journal: None,
+        })
+    }
+
+    // Node-wise `new_with_children` of two trees over the same number of positions, as if
+    // every leaf of `left` and `right` were stacked on top of each other. Both trees must not
+    // hold pending deltas.
+    pub(crate) fn new_stacked(left: &Self, right: &Self) -> Self {
+        Self {
+            nodes: left
+                .nodes
+                .iter()
+                .zip(right.nodes.iter())
+                .map(|(left_node, right_node)| NodeT::new_with_children(left_node, right_node))
+                .collect(),
+            arr_size: left.arr_size,
+            journal: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.arr_size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.arr_size == 0
+    }
+
+    // Checkpoints are positions in the journal, every later change can be undone by `rollback`
+    pub fn checkpoint(&mut self) -> usize {
+        self.journal.get_or_insert_with(Vec::new).len()
+    }
+
+    // Restores the tree to its state when `checkpoint` was taken, in time proportional to the
+    // number of node changes since then. Later checkpoints become invalid.
+    pub fn rollback(&mut self, checkpoint: usize) {
+        let journal = self
+            .journal
+            .as_mut()
+            .expect("no checkpoint to roll back to");
+        while journal.len() > checkpoint {
+            let (node_id, state) = journal.pop().unwrap();
+            self.nodes[node_id] = state;
+        }
+    }
+
+    // Drops the journal and every checkpoint, changes are no longer recorded
+    pub fn clear_checkpoints(&mut self) {
+        self.journal = None;
+    }
+
+    fn _record(&mut self, node_id: usize) {
+        if let Some(journal) = self.journal.as_mut() {
+            journal.push((node_id, self.nodes[node_id].clone()));
         }
     }
 
     fn _push_node(&mut self, node_id: usize, left: usize, right: usize) {
+        self._record(node_id);
         if left == right {
             self.nodes[node_id].push_leaf();
         } else {
+            self._record(node_id * 2 + 1);
+            self._record(node_id * 2 + 2);
             let (root_portion, rest) = self.nodes.split_at_mut(node_id + 1);
             let (l_nodes, r_nodes) = rest.split_at_mut(node_id * 2 + 2 - node_id - 1);
 
             let root_element = &mut root_portion[node_id];
-            root_element.push(&mut l_nodes[node_id * 2 + 1 - node_id - 1], &mut r_nodes[0]);
+            root_element.push(
+                &mut l_nodes[node_id * 2 + 1 - node_id - 1],
+                &mut r_nodes[0],
+                right - left + 1,
+            );
         }
     }
 
@@ -59,7 +134,11 @@ impl<NodeT: SegmentTreeState> SegmentTreeBmp<NodeT> {
             return;
         }
 
-        if left_update == left && right_update == right {
+        if left_update == left
+            && right_update == right
+            && (left == right || self.nodes[node_id].can_absorb(delta))
+        {
+            self._record(node_id);
             self.nodes[node_id].update_delta(delta);
             self._push_node(node_id, left, right);
             return;
@@ -84,27 +163,33 @@ impl<NodeT: SegmentTreeState> SegmentTreeBmp<NodeT> {
             cmp::max(left_update, middle + 1),
             right_update,
         );
+        self._record(node_id);
         let (root_portion, rest) = self.nodes.split_at_mut(node_id + 1);
         let root_element = &mut root_portion[node_id];
-        root_element.compute(
+        root_element.update_value(
             &rest[node_id * 2 + 1 - node_id - 1],
             &rest[node_id * 2 + 2 - node_id - 1],
         );
     }
 
     pub fn update(&mut self, left: usize, right: usize, delta: &NodeT::DeltaT) {
-        self._update_subtree(
-            delta,
-            0,
-            0,
-            ((self.arr_size as i32) - 1) as usize,
-            left,
-            right,
-        );
+        self._update_subtree(delta, 0, 0, self.arr_size - 1, left, right);
     }
 
-    fn _query_subtree(
+    pub fn try_update(
         &mut self,
+        left: usize,
+        right: usize,
+        delta: &NodeT::DeltaT,
+    ) -> Result<(), SegmentTreeError> {
+        check_range(left, right, self.len())?;
+        self.update(left, right, delta);
+        Ok(())
+    }
+
+    // Pending deltas are applied to the partial results on the way up instead of being pushed
+    fn _query_subtree(
+        &self,
         node_id: usize,
         left: usize,
         right: usize,
@@ -112,35 +197,259 @@ impl<NodeT: SegmentTreeState> SegmentTreeBmp<NodeT> {
         right_query: usize,
     ) -> NodeT::LeafT {
         if left_query > right_query {
-            return NodeT::LeafT::default();
+            return NodeT::identity();
         }
 
+        let value = if left_query == left && right_query == right {
+            self.nodes[node_id].get_value()
+        } else {
+            let middle = (left + right) / 2;
+            NodeT::reduce(
+                self._query_subtree(
+                    node_id * 2 + 1,
+                    left,
+                    middle,
+                    left_query,
+                    cmp::min(right_query, middle),
+                ),
+                self._query_subtree(
+                    node_id * 2 + 2,
+                    middle + 1,
+                    right,
+                    cmp::max(left_query, middle + 1),
+                    right_query,
+                ),
+            )
+        };
+        NodeT::apply_delta_to_value(
+            value,
+            &self.nodes[node_id].get_delta(),
+            right_query - left_query + 1,
+        )
+    }
+
+    pub fn query(&self, left: usize, right: usize) -> NodeT::LeafT {
+        self._query_subtree(0, 0, self.arr_size - 1, left, right)
+    }
+
+    pub fn try_query(&self, left: usize, right: usize) -> Result<NodeT::LeafT, SegmentTreeError> {
+        check_range(left, right, self.len())?;
+        Ok(self.query(left, right))
+    }
+
+    // Pushes the path down to `pos` and returns the id of its leaf
+    fn _get_subtree(&mut self, node_id: usize, left: usize, right: usize, pos: usize) -> usize {
+        self._push_node(node_id, left, right);
+        if left == right {
+            return node_id;
+        }
+
+        let middle = (left + right) / 2;
+
+        if pos <= middle {
+            self._get_subtree(node_id * 2 + 1, left, middle, pos)
+        } else {
+            self._get_subtree(node_id * 2 + 2, middle + 1, right, pos)
+        }
+    }
+
+    // Panics if pos is out of bounds, see try_get
+    pub fn get(&mut self, pos: usize) -> NodeT::LeafT {
+        self.try_get(pos).unwrap()
+    }
+
+    pub fn try_get(&mut self, pos: usize) -> Result<NodeT::LeafT, SegmentTreeError> {
+        check_index(pos, self.arr_size)?;
+        let leaf_id = self._get_subtree(0, 0, self.arr_size - 1, pos);
+        Ok(self.nodes[leaf_id].get_value())
+    }
+
+    pub(crate) fn get_leaf_state(&mut self, pos: usize) -> NodeT {
+        let leaf_id = self._get_subtree(0, 0, self.arr_size - 1, pos);
+        self.nodes[leaf_id].clone()
+    }
+
+    fn _set_subtree(
+        &mut self,
+        node_id: usize,
+        left: usize,
+        right: usize,
+        pos: usize,
+        state: NodeT,
+    ) {
         self._push_node(node_id, left, right);
-        if left_query == left && right_query == right {
-            return self.nodes[node_id].get_value();
+        if left == right {
+            self._record(node_id);
+            self.nodes[node_id] = state;
+            return;
         }
 
         let middle = (left + right) / 2;
 
-        return NodeT::merge(
-            self._query_subtree(
-                node_id * 2 + 1,
-                left,
-                middle,
-                left_query,
-                cmp::min(right_query, middle),
-            ),
-            self._query_subtree(
-                node_id * 2 + 2,
-                middle + 1,
-                right,
-                cmp::max(left_query, middle + 1),
-                right_query,
-            ),
+        if pos <= middle {
+            self._set_subtree(node_id * 2 + 1, left, middle, pos, state);
+            self._push_node(node_id * 2 + 2, middle + 1, right);
+        } else {
+            self._push_node(node_id * 2 + 1, left, middle);
+            self._set_subtree(node_id * 2 + 2, middle + 1, right, pos, state);
+        }
+        self._record(node_id);
+        let (root_portion, rest) = self.nodes.split_at_mut(node_id + 1);
+        let root_element = &mut root_portion[node_id];
+        root_element.update_value(
+            &rest[node_id * 2 + 1 - node_id - 1],
+            &rest[node_id * 2 + 2 - node_id - 1],
         );
     }
 
-    pub fn query(&mut self, left: usize, right: usize) -> NodeT::LeafT {
-        return self._query_subtree(0, 0, ((self.arr_size as i32) - 1) as usize, left, right);
+    // Panics if pos is out of bounds, see try_set
+    pub fn set(&mut self, pos: usize, element: &NodeT::ArrayT) {
+        self.try_set(pos, element).unwrap()
+    }
+
+    pub fn try_set(&mut self, pos: usize, element: &NodeT::ArrayT) -> Result<(), SegmentTreeError> {
+        check_index(pos, self.arr_size)?;
+        self._set_subtree(0, 0, self.arr_size - 1, pos, NodeT::new_leaf(element, pos));
+        Ok(())
+    }
+
+    pub(crate) fn set_leaf_state(&mut self, pos: usize, state: NodeT) {
+        self._set_subtree(0, 0, self.arr_size - 1, pos, state);
+    }
+
+    fn _collect_subtree(
+        &mut self,
+        node_id: usize,
+        left: usize,
+        right: usize,
+        values: &mut Vec<NodeT::LeafT>,
+    ) {
+        self._push_node(node_id, left, right);
+        if left == right {
+            values.push(self.nodes[node_id].get_value());
+            return;
+        }
+
+        let middle = (left + right) / 2;
+
+        self._collect_subtree(node_id * 2 + 1, left, middle, values);
+        self._collect_subtree(node_id * 2 + 2, middle + 1, right, values);
+    }
+
+    // Pushes every pending delta down to the leaves and returns the leaf values in order
+    pub fn to_vec(&mut self) -> Vec<NodeT::LeafT> {
+        let mut values = Vec::with_capacity(self.arr_size);
+        self._collect_subtree(0, 0, self.arr_size - 1, &mut values);
+        values
+    }
+
+    // Returns the first position >= left_search where pred stops holding for acc + prefix
+    fn _max_right_subtree<F: Fn(&NodeT::LeafT) -> bool>(
+        &mut self,
+        node_id: usize,
+        left: usize,
+        right: usize,
+        left_search: usize,
+        pred: &F,
+        acc: &mut NodeT::LeafT,
+    ) -> Option<usize> {
+        if right < left_search {
+            return None;
+        }
+
+        self._push_node(node_id, left, right);
+        if left_search <= left {
+            let value = NodeT::reduce(acc.clone(), self.nodes[node_id].get_value());
+            if pred(&value) {
+                *acc = value;
+                return None;
+            }
+            if left == right {
+                return Some(left);
+            }
+        }
+
+        let middle = (left + right) / 2;
+
+        match self._max_right_subtree(node_id * 2 + 1, left, middle, left_search, pred, acc) {
+            Some(pos) => Some(pos),
+            None => {
+                self._max_right_subtree(node_id * 2 + 2, middle + 1, right, left_search, pred, acc)
+            }
+        }
+    }
+
+    // Largest `right` with pred(query(left, right)), None if pred already fails on [left, left]
+    // or `left` is past the end.
+    // pred must be monotone (once false it stays false as the range grows) and hold for identity.
+    pub fn max_right<F: Fn(&NodeT::LeafT) -> bool>(
+        &mut self,
+        left: usize,
+        pred: F,
+    ) -> Option<usize> {
+        let last = self.arr_size - 1;
+        if left > last {
+            return None;
+        }
+        let mut acc = NodeT::identity();
+        match self._max_right_subtree(0, 0, last, left, &pred, &mut acc) {
+            Some(pos) if pos == left => None,
+            Some(pos) => Some(pos - 1),
+            None => Some(last),
+        }
+    }
+
+    // Returns the last position <= right_search where pred stops holding for suffix + acc
+    fn _min_left_subtree<F: Fn(&NodeT::LeafT) -> bool>(
+        &mut self,
+        node_id: usize,
+        left: usize,
+        right: usize,
+        right_search: usize,
+        pred: &F,
+        acc: &mut NodeT::LeafT,
+    ) -> Option<usize> {
+        if left > right_search {
+            return None;
+        }
+
+        self._push_node(node_id, left, right);
+        if right <= right_search {
+            let value = NodeT::reduce(self.nodes[node_id].get_value(), acc.clone());
+            if pred(&value) {
+                *acc = value;
+                return None;
+            }
+            if left == right {
+                return Some(left);
+            }
+        }
+
+        let middle = (left + right) / 2;
+
+        match self._min_left_subtree(node_id * 2 + 2, middle + 1, right, right_search, pred, acc) {
+            Some(pos) => Some(pos),
+            None => self._min_left_subtree(node_id * 2 + 1, left, middle, right_search, pred, acc),
+        }
+    }
+
+    // Smallest `left` with pred(query(left, right)), None if pred already fails on [right, right]
+    // or `right` is past the end.
+    // pred must be monotone (once false it stays false as the range grows) and hold for identity.
+    pub fn min_left<F: Fn(&NodeT::LeafT) -> bool>(
+        &mut self,
+        right: usize,
+        pred: F,
+    ) -> Option<usize> {
+        let last = self.arr_size - 1;
+        if right > last {
+            return None;
+        }
+        let mut acc = NodeT::identity();
+        match self._min_left_subtree(0, 0, last, right, &pred, &mut acc) {
+            Some(pos) if pos == right => None,
+            Some(pos) => Some(pos + 1),
+            None => Some(0),
+        }
     }
 }
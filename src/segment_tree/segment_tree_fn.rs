@@ -0,0 +1,214 @@
+use super::error::check_range;
+use super::error::SegmentTreeError;
+use core::cmp;
+
+// Lazy segment tree over the same flat layout as SegmentTreeBmp, with the monoid and its
+// deltas given as closures instead of a SegmentTreeState impl:
+// - `op` combines two adjacent ranges and `identity` is its neutral element
+// - `mapping(delta, value, len)` applies a delta to the value of `len` positions
+// - `composition(delta, next_delta)` is `delta` followed by `next_delta`, `id_delta` is a no-op
+// Unlike SegmentTreeState nodes, a node's value already includes its own pending delta, which
+// is only kept to be pushed into the children.
+// It cannot be a SegmentTreeBmp over an adapter node: `reduce`, `identity` and the other
+// SegmentTreeState value functions take no `self`, so a node type has no way to reach the
+// closures of a particular tree. The recursion is therefore a copy of SegmentTreeBmp's.
+pub struct SegmentTreeFn<S, F, Op, Mapping, Composition>
+where
+    S: Clone,
+    F: Clone,
+    Op: Fn(&S, &S) -> S,
+    Mapping: Fn(&F, &S, usize) -> S,
+    Composition: Fn(&F, &F) -> F,
+{
+    values: Vec<S>,
+    deltas: Vec<F>,
+    arr_size: usize,
+    identity: S,
+    id_delta: F,
+    op: Op,
+    mapping: Mapping,
+    composition: Composition,
+}
+
+impl<S, F, Op, Mapping, Composition> SegmentTreeFn<S, F, Op, Mapping, Composition>
+where
+    S: Clone,
+    F: Clone,
+    Op: Fn(&S, &S) -> S,
+    Mapping: Fn(&F, &S, usize) -> S,
+    Composition: Fn(&F, &F) -> F,
+{
+    fn _populate(&mut self, arr: &[S], node_id: usize, left: usize, right: usize) {
+        if left == right {
+            self.values[node_id] = arr[left].clone();
+            return;
+        }
+        let middle = (left + right) / 2;
+        self._populate(arr, node_id * 2 + 1, left, middle);
+        self._populate(arr, node_id * 2 + 2, middle + 1, right);
+        self.values[node_id] =
+            (self.op)(&self.values[node_id * 2 + 1], &self.values[node_id * 2 + 2]);
+    }
+
+    // Panics on an empty array, see try_new
+    pub fn new(
+        arr: &[S],
+        identity: S,
+        id_delta: F,
+        op: Op,
+        mapping: Mapping,
+        composition: Composition,
+    ) -> Self {
+        Self::try_new(arr, identity, id_delta, op, mapping, composition).unwrap()
+    }
+
+    pub fn try_new(
+        arr: &[S],
+        identity: S,
+        id_delta: F,
+        op: Op,
+        mapping: Mapping,
+        composition: Composition,
+    ) -> Result<Self, SegmentTreeError> {
+        if arr.is_empty() {
+            return Err(SegmentTreeError::EmptyInput);
+        }
+        let mut tree = Self {
+            values: vec![identity.clone(); arr.len() * 4],
+            deltas: vec![id_delta.clone(); arr.len() * 4],
+            arr_size: arr.len(),
+            identity,
+            id_delta,
+            op,
+            mapping,
+            composition,
+        };
+        tree._populate(arr, 0, 0, arr.len() - 1);
+        Ok(tree)
+    }
+
+    pub fn len(&self) -> usize {
+        self.arr_size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.arr_size == 0
+    }
+
+    fn _apply_node(&mut self, node_id: usize, left: usize, right: usize, delta: &F) {
+        self.values[node_id] = (self.mapping)(delta, &self.values[node_id], right - left + 1);
+        if left != right {
+            self.deltas[node_id] = (self.composition)(&self.deltas[node_id], delta);
+        }
+    }
+
+    fn _push_node(&mut self, node_id: usize, left: usize, right: usize) {
+        let delta = std::mem::replace(&mut self.deltas[node_id], self.id_delta.clone());
+        let middle = (left + right) / 2;
+        self._apply_node(node_id * 2 + 1, left, middle, &delta);
+        self._apply_node(node_id * 2 + 2, middle + 1, right, &delta);
+    }
+
+    fn _update_subtree(
+        &mut self,
+        delta: &F,
+        node_id: usize,
+        left: usize,
+        right: usize,
+        left_update: usize,
+        right_update: usize,
+    ) {
+        if left_update > right_update {
+            return;
+        }
+
+        if left_update == left && right_update == right {
+            self._apply_node(node_id, left, right, delta);
+            return;
+        }
+
+        let middle = (left + right) / 2;
+
+        self._push_node(node_id, left, right);
+        self._update_subtree(
+            delta,
+            node_id * 2 + 1,
+            left,
+            middle,
+            left_update,
+            cmp::min(right_update, middle),
+        );
+        self._update_subtree(
+            delta,
+            node_id * 2 + 2,
+            middle + 1,
+            right,
+            cmp::max(left_update, middle + 1),
+            right_update,
+        );
+        self.values[node_id] =
+            (self.op)(&self.values[node_id * 2 + 1], &self.values[node_id * 2 + 2]);
+    }
+
+    pub fn update(&mut self, left: usize, right: usize, delta: &F) {
+        self._update_subtree(delta, 0, 0, self.arr_size - 1, left, right);
+    }
+
+    pub fn try_update(
+        &mut self,
+        left: usize,
+        right: usize,
+        delta: &F,
+    ) -> Result<(), SegmentTreeError> {
+        check_range(left, right, self.len())?;
+        self.update(left, right, delta);
+        Ok(())
+    }
+
+    // Pending deltas are applied to the partial results on the way up instead of being pushed
+    fn _query_subtree(
+        &self,
+        node_id: usize,
+        left: usize,
+        right: usize,
+        left_query: usize,
+        right_query: usize,
+    ) -> S {
+        if left_query > right_query {
+            return self.identity.clone();
+        }
+
+        if left_query == left && right_query == right {
+            return self.values[node_id].clone();
+        }
+
+        let middle = (left + right) / 2;
+
+        let value = (self.op)(
+            &self._query_subtree(
+                node_id * 2 + 1,
+                left,
+                middle,
+                left_query,
+                cmp::min(right_query, middle),
+            ),
+            &self._query_subtree(
+                node_id * 2 + 2,
+                middle + 1,
+                right,
+                cmp::max(left_query, middle + 1),
+                right_query,
+            ),
+        );
+        (self.mapping)(&self.deltas[node_id], &value, right_query - left_query + 1)
+    }
+
+    pub fn query(&self, left: usize, right: usize) -> S {
+        self._query_subtree(0, 0, self.arr_size - 1, left, right)
+    }
+
+    pub fn try_query(&self, left: usize, right: usize) -> Result<S, SegmentTreeError> {
+        check_range(left, right, self.len())?;
+        Ok(self.query(left, right))
+    }
+}
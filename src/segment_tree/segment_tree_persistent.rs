@@ -0,0 +1,184 @@
+use super::error::SegmentTreeError;
+use super::segment_tree::CommutativeDelta;
+use super::segment_tree::SegmentTreeState;
+use core::cmp;
+use std::rc::Rc;
+
+// Tags are permanent: they stay on the node that absorbed them and are applied to query
+// results on the way up instead of being pushed into (shared) children. This is only
+// correct for deltas that commute with each other, hence the CommutativeDelta bound.
+struct PersistentNode<NodeT: SegmentTreeState> {
+    value: NodeT::LeafT,
+    tag: Option<NodeT::DeltaT>,
+    left_node: Option<Rc<PersistentNode<NodeT>>>,
+    right_node: Option<Rc<PersistentNode<NodeT>>>,
+}
+
+pub struct SegmentTreePersistent<NodeT: CommutativeDelta> {
+    roots: Vec<Rc<PersistentNode<NodeT>>>,
+    arr_size: usize,
+}
+
+impl<NodeT: CommutativeDelta> SegmentTreePersistent<NodeT> {
+    fn _new(arr: &[NodeT::ArrayT], left: usize, right: usize) -> Rc<PersistentNode<NodeT>> {
+        if left == right {
+            return Rc::new(PersistentNode {
+                value: NodeT::new_leaf(&arr[left], left).get_value(),
+                tag: None,
+                left_node: None,
+                right_node: None,
+            });
+        }
+        let middle = (left + right) / 2;
+        let left_node = Self::_new(arr, left, middle);
+        let right_node = Self::_new(arr, middle + 1, right);
+        Rc::new(PersistentNode {
+            value: NodeT::reduce(left_node.value.clone(), right_node.value.clone()),
+            tag: None,
+            left_node: Some(left_node),
+            right_node: Some(right_node),
+        })
+    }
+
+    // Panics on an empty array, see try_new
+    pub fn new(arr: &[NodeT::ArrayT]) -> Self {
+        Self::try_new(arr).unwrap()
+    }
+
+    pub fn try_new(arr: &[NodeT::ArrayT]) -> Result<Self, SegmentTreeError> {
+        if arr.is_empty() {
+            return Err(SegmentTreeError::EmptyInput);
+        }
+        Ok(Self {
+            roots: vec![Self::_new(arr, 0, arr.len() - 1)],
+            arr_size: arr.len(),
+        })
+    }
+
+    pub fn num_versions(&self) -> usize {
+        self.roots.len()
+    }
+
+    fn _update_subtree(
+        node: &Rc<PersistentNode<NodeT>>,
+        delta: &NodeT::DeltaT,
+        left: usize,
+        right: usize,
+        left_update: usize,
+        right_update: usize,
+    ) -> Rc<PersistentNode<NodeT>> {
+        if left_update > right_update {
+            return Rc::clone(node);
+        }
+
+        if left_update == left && right_update == right {
+            return Rc::new(PersistentNode {
+                value: NodeT::apply_delta_to_value(node.value.clone(), delta, right - left + 1),
+                tag: Some(match &node.tag {
+                    Some(tag) => NodeT::compose_delta(tag, delta),
+                    None => delta.clone(),
+                }),
+                left_node: node.left_node.clone(),
+                right_node: node.right_node.clone(),
+            });
+        }
+
+        let middle = (left + right) / 2;
+        let left_node = Self::_update_subtree(
+            node.left_node.as_ref().unwrap(),
+            delta,
+            left,
+            middle,
+            left_update,
+            cmp::min(right_update, middle),
+        );
+        let right_node = Self::_update_subtree(
+            node.right_node.as_ref().unwrap(),
+            delta,
+            middle + 1,
+            right,
+            cmp::max(left_update, middle + 1),
+            right_update,
+        );
+
+        let mut value = NodeT::reduce(left_node.value.clone(), right_node.value.clone());
+        if let Some(tag) = &node.tag {
+            value = NodeT::apply_delta_to_value(value, tag, right - left + 1);
+        }
+        Rc::new(PersistentNode {
+            value,
+            tag: node.tag.clone(),
+            left_node: Some(left_node),
+            right_node: Some(right_node),
+        })
+    }
+
+    // Applies the update on top of `version` and returns the handle of the new version
+    pub fn update(
+        &mut self,
+        version: usize,
+        left: usize,
+        right: usize,
+        delta: &NodeT::DeltaT,
+    ) -> usize {
+        let root = Self::_update_subtree(
+            &self.roots[version],
+            delta,
+            0,
+            self.arr_size - 1,
+            left,
+            cmp::min(right, self.arr_size - 1),
+        );
+        self.roots.push(root);
+        self.roots.len() - 1
+    }
+
+    fn _query_subtree(
+        node: &PersistentNode<NodeT>,
+        left: usize,
+        right: usize,
+        left_query: usize,
+        right_query: usize,
+    ) -> NodeT::LeafT {
+        if left_query > right_query {
+            return NodeT::identity();
+        }
+
+        if left_query == left && right_query == right {
+            return node.value.clone();
+        }
+
+        let middle = (left + right) / 2;
+
+        let value = NodeT::reduce(
+            Self::_query_subtree(
+                node.left_node.as_ref().unwrap(),
+                left,
+                middle,
+                left_query,
+                cmp::min(right_query, middle),
+            ),
+            Self::_query_subtree(
+                node.right_node.as_ref().unwrap(),
+                middle + 1,
+                right,
+                cmp::max(left_query, middle + 1),
+                right_query,
+            ),
+        );
+        match &node.tag {
+            Some(tag) => NodeT::apply_delta_to_value(value, tag, right_query - left_query + 1),
+            None => value,
+        }
+    }
+
+    pub fn query(&self, version: usize, left: usize, right: usize) -> NodeT::LeafT {
+        Self::_query_subtree(
+            &self.roots[version],
+            0,
+            self.arr_size - 1,
+            left,
+            cmp::min(right, self.arr_size - 1),
+        )
+    }
+}
@@ -0,0 +1,338 @@
+use super::error::SegmentTreeError;
+use super::segment_tree::MergeableLeaf;
+use super::segment_tree::SegmentTreeState;
+use core::cmp;
+use core::mem;
+
+struct SparseNode<NodeT: SegmentTreeState> {
+    node_state: NodeT,
+    left_node: Option<Box<SparseNode<NodeT>>>,
+    right_node: Option<Box<SparseNode<NodeT>>>,
+    // Set while the node may hold a delta it has not pushed yet, so a childless node without
+    // it still holds `element` everywhere
+    pending: bool,
+}
+
+// Children are only allocated once a node is pushed, an untouched subtree is a single node
+// built by `SegmentTreeState::new_range`. Nodes are indexed by the offset from `lo`, which is
+// also the position seen by the node states (e.g. in MinNode results).
+pub struct SegmentTreeSparse<NodeT: SegmentTreeState> {
+    root: SparseNode<NodeT>,
+    element: NodeT::ArrayT,
+    lo: i64,
+    hi: i64,
+    // Offset of `hi`
+    last: usize,
+}
+
+impl<NodeT: SegmentTreeState> SegmentTreeSparse<NodeT> {
+    // Every integer of [lo, hi] initially holds `element`. Panics on an empty domain, see try_new
+    pub fn new(lo: i64, hi: i64, element: NodeT::ArrayT) -> Self {
+        Self::try_new(lo, hi, element).unwrap()
+    }
+
+    // The number of positions must fit in a usize, which excludes the whole i64 domain
+    pub fn try_new(lo: i64, hi: i64, element: NodeT::ArrayT) -> Result<Self, SegmentTreeError> {
+        if lo > hi {
+            return Err(SegmentTreeError::EmptyInput);
+        }
+        let last = match usize::try_from(hi.abs_diff(lo)) {
+            Ok(last) if last < usize::MAX => last,
+            _ => return Err(SegmentTreeError::DomainTooLarge { lo, hi }),
+        };
+        Ok(Self {
+            root: Self::_new_node(&element, 0, last),
+            element,
+            lo,
+            hi,
+            last,
+        })
+    }
+
+    // Offsets of [left, right] clamped to the domain, None if nothing is left
+    fn _offsets(&self, left: i64, right: i64) -> Option<(usize, usize)> {
+        let (left, right) = (cmp::max(left, self.lo), cmp::min(right, self.hi));
+        if left > right {
+            return None;
+        }
+        Some((
+            left.abs_diff(self.lo) as usize,
+            right.abs_diff(self.lo) as usize,
+        ))
+    }
+
+    fn _new_node(element: &NodeT::ArrayT, left: usize, right: usize) -> SparseNode<NodeT> {
+        SparseNode {
+            node_state: NodeT::new_range(element, left, right),
+            left_node: None,
+            right_node: None,
+            pending: false,
+        }
+    }
+
+    fn _push_node(
+        node: &mut SparseNode<NodeT>,
+        element: &NodeT::ArrayT,
+        left: usize,
+        right: usize,
+    ) {
+        if left == right {
+            node.node_state.push_leaf();
+            node.pending = false;
+            return;
+        }
+
+        let middle = left + (right - left) / 2;
+        let left_node = node
+            .left_node
+            .get_or_insert_with(|| Box::new(Self::_new_node(element, left, middle)));
+        let right_node = node
+            .right_node
+            .get_or_insert_with(|| Box::new(Self::_new_node(element, middle + 1, right)));
+        node.node_state.push(
+            &mut left_node.node_state,
+            &mut right_node.node_state,
+            right - left + 1,
+        );
+        left_node.pending |= node.pending;
+        right_node.pending |= node.pending;
+        node.pending = false;
+    }
+
+    fn _update_subtree(
+        node: &mut SparseNode<NodeT>,
+        element: &NodeT::ArrayT,
+        delta: &NodeT::DeltaT,
+        left: usize,
+        right: usize,
+        left_update: usize,
+        right_update: usize,
+    ) {
+        if left_update > right_update {
+            Self::_push_node(node, element, left, right);
+            return;
+        }
+
+        if left_update == left
+            && right_update == right
+            && (left == right || node.node_state.can_absorb(delta))
+        {
+            node.node_state.update_delta(delta);
+            node.pending = true;
+            Self::_push_node(node, element, left, right);
+            return;
+        }
+
+        let middle = left + (right - left) / 2;
+
+        Self::_push_node(node, element, left, right);
+        let left_node = node.left_node.as_mut().unwrap();
+        let right_node = node.right_node.as_mut().unwrap();
+        Self::_update_subtree(
+            left_node,
+            element,
+            delta,
+            left,
+            middle,
+            left_update,
+            cmp::min(right_update, middle),
+        );
+        Self::_update_subtree(
+            right_node,
+            element,
+            delta,
+            middle + 1,
+            right,
+            cmp::max(left_update, middle + 1),
+            right_update,
+        );
+        node.node_state
+            .update_value(&left_node.node_state, &right_node.node_state);
+    }
+
+    pub fn update(&mut self, left: i64, right: i64, delta: &NodeT::DeltaT) {
+        if let Some((left, right)) = self._offsets(left, right) {
+            Self::_update_subtree(
+                &mut self.root,
+                &self.element,
+                delta,
+                0,
+                self.last,
+                left,
+                right,
+            );
+        }
+    }
+
+    fn _query_subtree(
+        node: &mut SparseNode<NodeT>,
+        element: &NodeT::ArrayT,
+        left: usize,
+        right: usize,
+        left_query: usize,
+        right_query: usize,
+    ) -> NodeT::LeafT {
+        if left_query > right_query {
+            return NodeT::identity();
+        }
+
+        Self::_push_node(node, element, left, right);
+        if left_query == left && right_query == right {
+            return node.node_state.get_value();
+        }
+
+        let middle = left + (right - left) / 2;
+
+        NodeT::reduce(
+            Self::_query_subtree(
+                node.left_node.as_mut().unwrap(),
+                element,
+                left,
+                middle,
+                left_query,
+                cmp::min(right_query, middle),
+            ),
+            Self::_query_subtree(
+                node.right_node.as_mut().unwrap(),
+                element,
+                middle + 1,
+                right,
+                cmp::max(left_query, middle + 1),
+                right_query,
+            ),
+        )
+    }
+
+    // `NodeT::identity()` if [left, right] does not intersect the domain
+    pub fn query(&mut self, left: i64, right: i64) -> NodeT::LeafT {
+        match self._offsets(left, right) {
+            Some((left, right)) => {
+                Self::_query_subtree(&mut self.root, &self.element, 0, self.last, left, right)
+            }
+            None => NodeT::identity(),
+        }
+    }
+
+    // Moves the positions >= pos of the subtree into a new subtree over the same range, the
+    // moved positions are reset to `element`. Both returned and kept nodes are pushed.
+    fn _split_subtree(
+        node: &mut SparseNode<NodeT>,
+        element: &NodeT::ArrayT,
+        left: usize,
+        right: usize,
+        pos: usize,
+    ) -> SparseNode<NodeT> {
+        if pos <= left {
+            let mut moved = mem::replace(node, Self::_new_node(element, left, right));
+            Self::_push_node(&mut moved, element, left, right);
+            Self::_push_node(node, element, left, right);
+            return moved;
+        }
+
+        let mut split = Self::_new_node(element, left, right);
+        if pos > right {
+            Self::_push_node(&mut split, element, left, right);
+            Self::_push_node(node, element, left, right);
+            return split;
+        }
+
+        let middle = left + (right - left) / 2;
+
+        Self::_push_node(node, element, left, right);
+        let left_node = node.left_node.as_mut().unwrap();
+        let right_node = node.right_node.as_mut().unwrap();
+        let split_left = Self::_split_subtree(left_node, element, left, middle, pos);
+        let split_right = Self::_split_subtree(right_node, element, middle + 1, right, pos);
+        node.node_state
+            .update_value(&left_node.node_state, &right_node.node_state);
+        split.node_state =
+            NodeT::new_with_children(&split_left.node_state, &split_right.node_state);
+        split.left_node = Some(Box::new(split_left));
+        split.right_node = Some(Box::new(split_right));
+        split
+    }
+
+    // Keeps the positions < pos and returns a tree over the same domain holding the positions
+    // >= pos, every other position of either tree holds `element`. O(log n) new nodes.
+    pub fn split_at(&mut self, pos: i64) -> Self
+    where
+        NodeT::ArrayT: Clone,
+    {
+        let offset = match self._offsets(pos, self.hi) {
+            Some((offset, _)) => offset,
+            None => self.last + 1,
+        };
+        let root = Self::_split_subtree(&mut self.root, &self.element, 0, self.last, offset);
+        Self {
+            root,
+            element: self.element.clone(),
+            lo: self.lo,
+            hi: self.hi,
+            last: self.last,
+        }
+    }
+}
+
+impl<NodeT: MergeableLeaf> SegmentTreeSparse<NodeT> {
+    // A node without children that is not a leaf was never pushed, so unless it is pending its
+    // subtree still holds `element` and is skipped. Pending ones are expanded like the others.
+    fn _merge_subtree(
+        node: &mut SparseNode<NodeT>,
+        mut other: SparseNode<NodeT>,
+        element: &NodeT::ArrayT,
+        left: usize,
+        right: usize,
+    ) {
+        if left == right {
+            node.node_state.push_leaf();
+            other.node_state.push_leaf();
+            node.node_state = NodeT::new_leaf_from_value(NodeT::reduce(
+                node.node_state.get_value(),
+                other.node_state.get_value(),
+            ));
+            return;
+        }
+
+        if other.left_node.is_none() && !other.pending {
+            Self::_push_node(node, element, left, right);
+            return;
+        }
+        if node.left_node.is_none() && !node.pending {
+            *node = other;
+            Self::_push_node(node, element, left, right);
+            return;
+        }
+
+        let middle = left + (right - left) / 2;
+
+        Self::_push_node(node, element, left, right);
+        Self::_push_node(&mut other, element, left, right);
+        let left_node = node.left_node.as_mut().unwrap();
+        let right_node = node.right_node.as_mut().unwrap();
+        Self::_merge_subtree(left_node, *other.left_node.unwrap(), element, left, middle);
+        Self::_merge_subtree(
+            right_node,
+            *other.right_node.unwrap(),
+            element,
+            middle + 1,
+            right,
+        );
+        node.node_state
+            .update_value(&left_node.node_state, &right_node.node_state);
+    }
+
+    // Position-wise `reduce` of both trees, which must have the same domain. Untouched subtrees
+    // are skipped, so `element` must be neutral for `reduce` (0 for sums or counts). With point
+    // updates every step discards a node of `other`, so merging small-to-large is amortized
+    // O(log n) per node ever created. A range update leaves a pending childless node, which is
+    // expanded down to the nodes of the other tree below it, and down to the leaves where the
+    // other tree holds a pending childless node over the same range.
+    pub fn merge(&mut self, other: Self) {
+        assert_eq!(
+            (self.lo, self.hi),
+            (other.lo, other.hi),
+            "trees over different domains"
+        );
+        Self::_merge_subtree(&mut self.root, other.root, &self.element, 0, self.last);
+    }
+}
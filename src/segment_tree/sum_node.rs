@@ -1,45 +1,61 @@
+use super::segment_tree::AdditiveDelta;
+use super::segment_tree::CommutativeDelta;
+use super::segment_tree::MergeableLeaf;
 use super::segment_tree::SegmentTreeState;
+use super::sum_value::SumValue;
 
 #[derive(Debug, Clone)]
-pub struct SumNode {
-    sum: i64,
-    delta: i64,
-    left: usize,  // could be optimized away
-    right: usize, // could be optimized away
+pub struct SumNode<T: SumValue = i64> {
+    sum: T,
+    delta: T,
 }
 
-impl SegmentTreeState for SumNode {
-    type LeafT = i64;
-    type DeltaT = i64;
+impl<T: SumValue> SegmentTreeState for SumNode<T> {
+    type ArrayT = T;
+    type LeafT = T;
+    type DeltaT = T;
 
-    fn merge(a: Self::LeafT, b: Self::LeafT) -> Self::LeafT {
-        a + b
+    fn reduce(a: Self::LeafT, b: Self::LeafT) -> Self::LeafT {
+        a.plus(b)
+    }
+
+    fn identity() -> Self::LeafT {
+        T::zero()
+    }
+
+    fn compose_delta(delta: &Self::DeltaT, next_delta: &Self::DeltaT) -> Self::DeltaT {
+        delta.plus(*next_delta)
+    }
+
+    fn apply_delta_to_value(value: Self::LeafT, delta: &Self::DeltaT, len: usize) -> Self::LeafT {
+        value.plus(delta.times(len))
     }
 
     fn new() -> Self {
         Self {
-            sum: 0,
-            delta: 0,
-            left: 0,
-            right: 0,
+            sum: T::zero(),
+            delta: T::zero(),
+        }
+    }
+
+    fn new_leaf(element: &Self::ArrayT, _leaf_pos: usize) -> Self {
+        Self {
+            sum: *element,
+            delta: T::zero(),
         }
     }
 
-    fn new_with_leaf(leaf_value: Self::LeafT, leaf_pos: usize) -> Self {
+    fn new_range(element: &Self::ArrayT, left: usize, right: usize) -> Self {
         Self {
-            sum: leaf_value,
-            delta: 0,
-            left: leaf_pos,
-            right: leaf_pos,
+            sum: element.times(right - left + 1),
+            delta: T::zero(),
         }
     }
 
     fn new_with_children(left_child: &Self, right_child: &Self) -> Self {
         Self {
-            sum: left_child.sum + right_child.sum,
-            delta: 0,
-            left: left_child.left,
-            right: right_child.right,
+            sum: left_child.sum.plus(right_child.sum),
+            delta: T::zero(),
         }
     }
 
@@ -48,29 +64,39 @@ impl SegmentTreeState for SumNode {
     }
 
     fn update_delta(&mut self, delta: &Self::DeltaT) {
-        self.delta += delta;
+        self.delta = self.delta.plus(*delta);
     }
 
     fn get_value(&self) -> Self::LeafT {
         self.sum
     }
 
-    fn push(&mut self, left_child: &mut Self, right_child: &mut Self) {
-        self.sum += ((self.right as i64) - (self.left as i64) + 1) * self.delta;
-        left_child.delta += self.delta;
-        right_child.delta += self.delta;
-        self.delta = 0;
+    fn push(&mut self, left_child: &mut Self, right_child: &mut Self, len: usize) {
+        self.sum = self.sum.plus(self.delta.times(len));
+        left_child.update_delta(&self.delta);
+        right_child.update_delta(&self.delta);
+        self.delta = T::zero();
     }
 
     fn push_leaf(&mut self) {
-        self.sum += self.delta;
-        self.delta = 0;
+        self.sum = self.sum.plus(self.delta);
+        self.delta = T::zero();
+    }
+
+    fn update_value(&mut self, left_child: &Self, right_child: &Self) {
+        self.sum = left_child.sum.plus(right_child.sum);
     }
+}
+
+impl<T: SumValue> CommutativeDelta for SumNode<T> {}
 
-    fn compute(&mut self, left_child: &Self, right_child: &Self) {
-        // The assert should be removed for efficiency
-        assert!(self.delta == 0 && left_child.delta == 0 && right_child.delta == 0);
+impl<T: SumValue> AdditiveDelta for SumNode<T> {}
 
-        self.sum = left_child.sum + right_child.sum;
+impl<T: SumValue> MergeableLeaf for SumNode<T> {
+    fn new_leaf_from_value(value: Self::LeafT) -> Self {
+        Self {
+            sum: value,
+            delta: T::zero(),
+        }
     }
 }
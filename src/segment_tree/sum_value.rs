@@ -0,0 +1,118 @@
+use std::num::Saturating;
+use std::num::Wrapping;
+
+// Commutative monoid under addition for SumNode, `times` is repeated addition and only needs
+// overriding for speed, so custom (e.g. modular) types just provide `zero` and `plus`.
+// The overflow behaviour is picked by the type: plain integers panic in debug builds,
+// Wrapping<T> wraps, Saturating<T> clamps and Checked<T> turns into None.
+pub trait SumValue: Copy {
+    fn zero() -> Self;
+    fn plus(self, other: Self) -> Self;
+
+    fn times(self, n: usize) -> Self {
+        let mut result = Self::zero();
+        let mut power = self;
+        let mut n = n;
+        while n > 0 {
+            if n & 1 == 1 {
+                result = result.plus(power);
+            }
+            power = power.plus(power);
+            n >>= 1;
+        }
+        result
+    }
+}
+
+// A length that does not fit the type overflows the product unless the value is zero, so it
+// panics in every build instead of being truncated by `as`
+macro_rules! impl_sum_value {
+    ($($t:ty),*) => {
+        $(
+            impl SumValue for $t {
+                fn zero() -> Self {
+                    0
+                }
+
+                fn plus(self, other: Self) -> Self {
+                    self + other
+                }
+
+                fn times(self, n: usize) -> Self {
+                    if self == 0 {
+                        return 0;
+                    }
+                    self * <$t>::try_from(n).expect("length does not fit the sum type")
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_sum_value_float {
+    ($($t:ty),*) => {
+        $(
+            impl SumValue for $t {
+                fn zero() -> Self {
+                    0.0
+                }
+
+                fn plus(self, other: Self) -> Self {
+                    self + other
+                }
+
+                fn times(self, n: usize) -> Self {
+                    self * (n as $t)
+                }
+            }
+        )*
+    };
+}
+
+// `n as $t` truncates modulo 2^bits, which is all wrapping multiplication needs
+macro_rules! impl_sum_value_wrapping {
+    ($($t:ty),*) => {
+        $(
+            impl SumValue for Wrapping<$t> {
+                fn zero() -> Self {
+                    Wrapping(0)
+                }
+
+                fn plus(self, other: Self) -> Self {
+                    self + other
+                }
+
+                fn times(self, n: usize) -> Self {
+                    self * Wrapping(n as $t)
+                }
+            }
+        )*
+    };
+}
+
+// Clamping is only order independent while every value has the same sign, mixed signs make
+// the result depend on how the tree groups the additions
+macro_rules! impl_sum_value_saturating {
+    ($($t:ty),*) => {
+        $(
+            impl SumValue for Saturating<$t> {
+                fn zero() -> Self {
+                    Saturating(0)
+                }
+
+                fn plus(self, other: Self) -> Self {
+                    self + other
+                }
+
+                fn times(self, n: usize) -> Self {
+                    Saturating(self.0.saturating_mul(<$t>::try_from(n).unwrap_or(<$t>::MAX)))
+                }
+            }
+        )*
+    };
+}
+
+impl_sum_value!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+impl_sum_value_float!(f32, f64);
+impl_sum_value_wrapping!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+impl_sum_value_saturating!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
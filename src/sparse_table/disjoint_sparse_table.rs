@@ -0,0 +1,62 @@
+use crate::segment_tree::SegmentTreeState;
+
+// Static range queries in O(1) after an O(n log n) build for any associative `reduce`.
+// On level b the array is cut into blocks of 2^(b + 1) positions; every position keeps the
+// value from itself to the middle of its block, so [left, right] with highest differing bit
+// b is answered by the two halves meeting at that middle.
+pub struct DisjointSparseTable<NodeT: SegmentTreeState> {
+    leaves: Vec<NodeT::LeafT>,
+    table: Vec<Vec<NodeT::LeafT>>,
+}
+
+impl<NodeT: SegmentTreeState> DisjointSparseTable<NodeT> {
+    pub fn new(arr: &[NodeT::ArrayT]) -> Self {
+        let leaves: Vec<NodeT::LeafT> = arr
+            .iter()
+            .enumerate()
+            .map(|(pos, element)| NodeT::new_leaf(element, pos).get_value())
+            .collect();
+
+        let mut table = Vec::new();
+        let mut bit = 0;
+        while (1 << bit) < leaves.len() {
+            let mut level = leaves.clone();
+            for start in (0..leaves.len()).step_by(1 << (bit + 1)) {
+                let middle = start + (1 << bit);
+                if middle >= leaves.len() {
+                    break;
+                }
+                for pos in (start..middle - 1).rev() {
+                    level[pos] = NodeT::reduce(leaves[pos].clone(), level[pos + 1].clone());
+                }
+                let end = (start + (1 << (bit + 1))).min(leaves.len());
+                for pos in middle + 1..end {
+                    level[pos] = NodeT::reduce(level[pos - 1].clone(), leaves[pos].clone());
+                }
+            }
+            table.push(level);
+            bit += 1;
+        }
+
+        Self { leaves, table }
+    }
+
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    pub fn query(&self, left: usize, right: usize) -> NodeT::LeafT {
+        if left == right {
+            return self.leaves[left].clone();
+        }
+        let bit = (usize::BITS - 1 - (left ^ right).leading_zeros()) as usize;
+        NodeT::reduce(
+            self.table[bit][left].clone(),
+            self.table[bit][right].clone(),
+        )
+    }
+}
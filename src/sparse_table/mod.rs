@@ -0,0 +1,6 @@
+pub mod disjoint_sparse_table;
+#[allow(clippy::module_inception)]
+pub mod sparse_table;
+
+pub use disjoint_sparse_table::DisjointSparseTable;
+pub use sparse_table::SparseTable;
@@ -0,0 +1,47 @@
+use crate::segment_tree::SegmentTreeState;
+
+// Static range queries in O(1) after an O(n log n) build, reusing the leaves and `reduce` of a
+// node type. The two blocks of a query overlap, so `reduce` must be idempotent (MinNode,
+// MaxNode, GcdNode, AndNode, ...); use DisjointSparseTable for anything else.
+pub struct SparseTable<NodeT: SegmentTreeState> {
+    // table[k][i] is the value of [i, i + 2^k)
+    table: Vec<Vec<NodeT::LeafT>>,
+}
+
+impl<NodeT: SegmentTreeState> SparseTable<NodeT> {
+    pub fn new(arr: &[NodeT::ArrayT]) -> Self {
+        let leaves: Vec<NodeT::LeafT> = arr
+            .iter()
+            .enumerate()
+            .map(|(pos, element)| NodeT::new_leaf(element, pos).get_value())
+            .collect();
+        let mut table = vec![leaves];
+        let mut k = 1;
+        while (1 << k) <= arr.len() {
+            let previous = &table[k - 1];
+            let half = 1 << (k - 1);
+            let level = (0..=arr.len() - (1 << k))
+                .map(|pos| NodeT::reduce(previous[pos].clone(), previous[pos + half].clone()))
+                .collect();
+            table.push(level);
+            k += 1;
+        }
+        Self { table }
+    }
+
+    pub fn len(&self) -> usize {
+        self.table[0].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn query(&self, left: usize, right: usize) -> NodeT::LeafT {
+        let k = (usize::BITS - 1 - (right - left + 1).leading_zeros()) as usize;
+        NodeT::reduce(
+            self.table[k][left].clone(),
+            self.table[k][right + 1 - (1 << k)].clone(),
+        )
+    }
+}
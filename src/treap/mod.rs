@@ -0,0 +1,4 @@
+#[allow(clippy::module_inception)]
+pub mod treap;
+
+pub use treap::Treap;
@@ -0,0 +1,239 @@
+use crate::segment_tree::SegmentTreeState;
+use core::mem;
+
+type Link<NodeT> = Option<Box<TreapNode<NodeT>>>;
+
+// `element` and `value` (the aggregate of the whole subtree) already include the node's own
+// delta and reversal, which are only kept to be pushed into the children
+struct TreapNode<NodeT: SegmentTreeState> {
+    element: NodeT::LeafT,
+    value: NodeT::LeafT,
+    delta: Option<NodeT::DeltaT>,
+    reversed: bool,
+    size: usize,
+    priority: u64,
+    left_node: Link<NodeT>,
+    right_node: Link<NodeT>,
+}
+
+// Implicit treap: a sequence keyed by position supporting insert, erase, split, merge and
+// range reverse on top of the SegmentTreeState value functions. Only `new_leaf(..).get_value()`
+// is taken from the node states, so the `leaf_pos` seen by nodes is the position at insertion
+// and nodes relying on `can_absorb` (segment tree beats) are not supported.
+pub struct Treap<NodeT: SegmentTreeState> {
+    root: Link<NodeT>,
+    seed: u64,
+}
+
+impl<NodeT: SegmentTreeState> Treap<NodeT> {
+    pub fn new(arr: &[NodeT::ArrayT]) -> Self {
+        let mut treap = Self {
+            root: None,
+            seed: 0x9E37_79B9_7F4A_7C15,
+        };
+        for element in arr.iter() {
+            treap.insert(treap.len(), element);
+        }
+        treap
+    }
+
+    pub fn len(&self) -> usize {
+        Self::_size(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    // xorshift64*
+    fn _next_priority(&mut self) -> u64 {
+        self.seed ^= self.seed >> 12;
+        self.seed ^= self.seed << 25;
+        self.seed ^= self.seed >> 27;
+        self.seed.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    fn _size(link: &Link<NodeT>) -> usize {
+        link.as_ref().map_or(0, |node| node.size)
+    }
+
+    fn _apply_delta(node: &mut TreapNode<NodeT>, delta: &NodeT::DeltaT) {
+        node.element = NodeT::apply_delta_to_value(node.element.clone(), delta, 1);
+        node.value = NodeT::apply_delta_to_value(node.value.clone(), delta, node.size);
+        node.delta = Some(match &node.delta {
+            Some(node_delta) => NodeT::compose_delta(node_delta, delta),
+            None => delta.clone(),
+        });
+    }
+
+    fn _toggle_reversed(node: &mut TreapNode<NodeT>) {
+        mem::swap(&mut node.left_node, &mut node.right_node);
+        node.value = NodeT::reverse_value(node.value.clone());
+        node.reversed = !node.reversed;
+    }
+
+    fn _push_node(node: &mut TreapNode<NodeT>) {
+        if let Some(delta) = node.delta.take() {
+            for child in [&mut node.left_node, &mut node.right_node] {
+                if let Some(child) = child.as_mut() {
+                    Self::_apply_delta(child, &delta);
+                }
+            }
+        }
+        if node.reversed {
+            for child in [&mut node.left_node, &mut node.right_node] {
+                if let Some(child) = child.as_mut() {
+                    Self::_toggle_reversed(child);
+                }
+            }
+            node.reversed = false;
+        }
+    }
+
+    fn _compute_node(node: &mut TreapNode<NodeT>) {
+        node.size = 1 + Self::_size(&node.left_node) + Self::_size(&node.right_node);
+        let mut value = node.element.clone();
+        if let Some(left_node) = node.left_node.as_ref() {
+            value = NodeT::reduce(left_node.value.clone(), value);
+        }
+        if let Some(right_node) = node.right_node.as_ref() {
+            value = NodeT::reduce(value, right_node.value.clone());
+        }
+        node.value = value;
+    }
+
+    // First `pos` elements on the left, the rest on the right
+    fn _split(link: Link<NodeT>, pos: usize) -> (Link<NodeT>, Link<NodeT>) {
+        let mut node = match link {
+            Some(node) => node,
+            None => return (None, None),
+        };
+
+        Self::_push_node(&mut node);
+        let left_size = Self::_size(&node.left_node);
+        if pos <= left_size {
+            let (left, right) = Self::_split(node.left_node.take(), pos);
+            node.left_node = right;
+            Self::_compute_node(&mut node);
+            (left, Some(node))
+        } else {
+            let (left, right) = Self::_split(node.right_node.take(), pos - left_size - 1);
+            node.right_node = left;
+            Self::_compute_node(&mut node);
+            (Some(node), right)
+        }
+    }
+
+    fn _merge(left: Link<NodeT>, right: Link<NodeT>) -> Link<NodeT> {
+        match (left, right) {
+            (None, right) => right,
+            (left, None) => left,
+            (Some(mut left), Some(mut right)) => {
+                if left.priority > right.priority {
+                    Self::_push_node(&mut left);
+                    left.right_node = Self::_merge(left.right_node.take(), Some(right));
+                    Self::_compute_node(&mut left);
+                    Some(left)
+                } else {
+                    Self::_push_node(&mut right);
+                    right.left_node = Self::_merge(Some(left), right.left_node.take());
+                    Self::_compute_node(&mut right);
+                    Some(right)
+                }
+            }
+        }
+    }
+
+    // Elements at positions >= pos go to the second treap
+    pub fn split(self, pos: usize) -> (Self, Self) {
+        let (left, right) = Self::_split(self.root, pos);
+        (
+            Self {
+                root: left,
+                seed: self.seed,
+            },
+            Self {
+                root: right,
+                seed: self.seed.rotate_left(32),
+            },
+        )
+    }
+
+    // Concatenation of `left` followed by `right`
+    pub fn merge(left: Self, right: Self) -> Self {
+        Self {
+            root: Self::_merge(left.root, right.root),
+            seed: left.seed,
+        }
+    }
+
+    pub fn insert(&mut self, pos: usize, element: &NodeT::ArrayT) {
+        let value = NodeT::new_leaf(element, pos).get_value();
+        let node = Box::new(TreapNode {
+            element: value.clone(),
+            value,
+            delta: None,
+            reversed: false,
+            size: 1,
+            priority: self._next_priority(),
+            left_node: None,
+            right_node: None,
+        });
+        let (left, right) = Self::_split(self.root.take(), pos);
+        self.root = Self::_merge(Self::_merge(left, Some(node)), right);
+    }
+
+    // Removes the element at `pos` and returns its value
+    pub fn erase(&mut self, pos: usize) -> NodeT::LeafT {
+        let (left, right) = Self::_split(self.root.take(), pos);
+        let (middle, right) = Self::_split(right, 1);
+        self.root = Self::_merge(left, right);
+        middle.unwrap().element
+    }
+
+    // Runs `f` on the subtree holding exactly [left, right]
+    fn _with_range<R, F: FnOnce(&mut TreapNode<NodeT>) -> R>(
+        &mut self,
+        left: usize,
+        right: usize,
+        f: F,
+    ) -> R {
+        let (first, rest) = Self::_split(self.root.take(), left);
+        let (mut middle, last) = Self::_split(rest, right - left + 1);
+        let result = f(middle.as_mut().unwrap());
+        self.root = Self::_merge(Self::_merge(first, middle), last);
+        result
+    }
+
+    pub fn update(&mut self, left: usize, right: usize, delta: &NodeT::DeltaT) {
+        self._with_range(left, right, |node| Self::_apply_delta(node, delta));
+    }
+
+    pub fn reverse(&mut self, left: usize, right: usize) {
+        self._with_range(left, right, Self::_toggle_reversed);
+    }
+
+    pub fn query(&mut self, left: usize, right: usize) -> NodeT::LeafT {
+        self._with_range(left, right, |node| node.value.clone())
+    }
+
+    pub fn get(&mut self, pos: usize) -> NodeT::LeafT {
+        self.query(pos, pos)
+    }
+
+    fn _collect(link: &mut Link<NodeT>, values: &mut Vec<NodeT::LeafT>) {
+        if let Some(node) = link.as_mut() {
+            Self::_push_node(node);
+            Self::_collect(&mut node.left_node, values);
+            values.push(node.element.clone());
+            Self::_collect(&mut node.right_node, values);
+        }
+    }
+
+    // Pushes every pending delta and reversal down and returns the element values in order
+    pub fn to_vec(&mut self) -> Vec<NodeT::LeafT> {
+        let mut values = Vec::with_capacity(self.len());
+        Self::_collect(&mut self.root, &mut values);
+        values
+    }
+}
@@ -0,0 +1,37 @@
+// Bits packed in 64-bit blocks, with the number of ones before every block for O(1) rank
+pub(crate) struct BitVector {
+    blocks: Vec<u64>,
+    ones_before: Vec<usize>,
+}
+
+impl BitVector {
+    pub(crate) fn new(bits: &[bool]) -> Self {
+        let mut blocks = vec![0u64; bits.len() / 64 + 1];
+        for (pos, bit) in bits.iter().enumerate() {
+            if *bit {
+                blocks[pos / 64] |= 1 << (pos % 64);
+            }
+        }
+        let mut ones_before = Vec::with_capacity(blocks.len());
+        let mut ones = 0;
+        for block in blocks.iter() {
+            ones_before.push(ones);
+            ones += block.count_ones() as usize;
+        }
+        Self {
+            blocks,
+            ones_before,
+        }
+    }
+
+    // Number of ones in [0, pos)
+    pub(crate) fn rank1(&self, pos: usize) -> usize {
+        let mask = (1u64 << (pos % 64)) - 1;
+        self.ones_before[pos / 64] + (self.blocks[pos / 64] & mask).count_ones() as usize
+    }
+
+    // Number of zeros in [0, pos)
+    pub(crate) fn rank0(&self, pos: usize) -> usize {
+        pos - self.rank1(pos)
+    }
+}
@@ -0,0 +1,5 @@
+mod bit_vector;
+#[allow(clippy::module_inception)]
+pub mod wavelet_matrix;
+
+pub use wavelet_matrix::WaveletMatrix;
@@ -0,0 +1,178 @@
+use super::bit_vector::BitVector;
+use core::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+// Static order statistics over ranges. Values are compressed to their index among the sorted
+// distinct values, then level i holds bit `bits - 1 - i` of every code, with the codes stably
+// partitioned by that bit (zeros first) before moving to the next level.
+// Queries take inclusive ranges and panic unless left <= right < len.
+pub struct WaveletMatrix<T: Ord + Clone> {
+    values: Vec<T>,
+    levels: Vec<BitVector>,
+    zeros: Vec<usize>,
+    arr_size: usize,
+}
+
+impl<T: Ord + Clone> WaveletMatrix<T> {
+    pub fn new(arr: &[T]) -> Self {
+        let mut values = arr.to_vec();
+        values.sort();
+        values.dedup();
+
+        let mut bits = 1;
+        while (1 << bits) < values.len() {
+            bits += 1;
+        }
+
+        let mut codes: Vec<usize> = arr
+            .iter()
+            .map(|element| values.binary_search(element).unwrap())
+            .collect();
+        let mut levels = Vec::with_capacity(bits);
+        let mut zeros = Vec::with_capacity(bits);
+        for level in 0..bits {
+            let shift = bits - 1 - level;
+            let level_bits: Vec<bool> = codes.iter().map(|code| (code >> shift) & 1 == 1).collect();
+            let (mut next_codes, ones): (Vec<usize>, Vec<usize>) =
+                codes.iter().partition(|code| (*code >> shift) & 1 == 0);
+            zeros.push(next_codes.len());
+            next_codes.extend(ones);
+            levels.push(BitVector::new(&level_bits));
+            codes = next_codes;
+        }
+
+        Self {
+            values,
+            levels,
+            zeros,
+            arr_size: arr.len(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.arr_size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.arr_size == 0
+    }
+
+    fn _check_range(&self, left: usize, right: usize) {
+        assert!(
+            left <= right && right < self.arr_size,
+            "invalid range [{}, {}] for length {}",
+            left,
+            right,
+            self.arr_size
+        );
+    }
+
+    // Maps the half-open [left, right) of `level` to the positions of its elements with the
+    // given bit on the next level
+    fn _child(&self, level: usize, left: usize, right: usize, bit: bool) -> (usize, usize) {
+        let bit_vector = &self.levels[level];
+        if bit {
+            (
+                self.zeros[level] + bit_vector.rank1(left),
+                self.zeros[level] + bit_vector.rank1(right),
+            )
+        } else {
+            (bit_vector.rank0(left), bit_vector.rank0(right))
+        }
+    }
+
+    // Number of codes below `code` in the half-open [left, right)
+    fn _count_less(&self, left: usize, right: usize, code: usize) -> usize {
+        if code >= 1 << self.levels.len() {
+            return right - left;
+        }
+
+        let (mut left, mut right) = (left, right);
+        let mut result = 0;
+        for level in 0..self.levels.len() {
+            let bit = (code >> (self.levels.len() - 1 - level)) & 1 == 1;
+            if bit {
+                let bit_vector = &self.levels[level];
+                result += bit_vector.rank0(right) - bit_vector.rank0(left);
+            }
+            (left, right) = self._child(level, left, right, bit);
+        }
+        result
+    }
+
+    // k-th (0-based) smallest value of [left, right], None if the range has at most k elements
+    pub fn kth_smallest(&self, left: usize, right: usize, k: usize) -> Option<T> {
+        self._check_range(left, right);
+        if k > right - left {
+            return None;
+        }
+
+        let (mut left, mut right) = (left, right + 1);
+        let mut k = k;
+        let mut code = 0;
+        for level in 0..self.levels.len() {
+            let bit_vector = &self.levels[level];
+            let zeros = bit_vector.rank0(right) - bit_vector.rank0(left);
+            let bit = k >= zeros;
+            if bit {
+                k -= zeros;
+                code |= 1 << (self.levels.len() - 1 - level);
+            }
+            (left, right) = self._child(level, left, right, bit);
+        }
+        Some(self.values[code].clone())
+    }
+
+    // Number of elements of [left, right] strictly smaller than `value`
+    pub fn rank(&self, left: usize, right: usize, value: &T) -> usize {
+        self._check_range(left, right);
+        let code = self.values.partition_point(|x| x < value);
+        self._count_less(left, right + 1, code)
+    }
+
+    // Number of elements of [left, right] within [lo, hi]
+    pub fn count_range(&self, left: usize, right: usize, lo: &T, hi: &T) -> usize {
+        self._check_range(left, right);
+        if lo > hi {
+            return 0;
+        }
+        let lo_code = self.values.partition_point(|x| x < lo);
+        let hi_code = self.values.partition_point(|x| x <= hi);
+        self._count_less(left, right + 1, hi_code) - self._count_less(left, right + 1, lo_code)
+    }
+
+    // The k most frequent values of [left, right] with their counts, most frequent first and
+    // smaller values first among equal counts
+    pub fn top_k(&self, left: usize, right: usize, k: usize) -> Vec<(T, usize)> {
+        self._check_range(left, right);
+        let mut result = Vec::with_capacity(k);
+        // Codes of partial nodes keep their lower bits at zero, i.e. the smallest code below them
+        let mut heap = BinaryHeap::new();
+        heap.push((right + 1 - left, Reverse(0usize), 0usize, left, right + 1));
+        while let Some((count, Reverse(code), level, left, right)) = heap.pop() {
+            if result.len() == k {
+                break;
+            }
+            if level == self.levels.len() {
+                result.push((self.values[code].clone(), count));
+                continue;
+            }
+
+            let shift = self.levels.len() - 1 - level;
+            for bit in [false, true] {
+                let (child_left, child_right) = self._child(level, left, right, bit);
+                if child_left < child_right {
+                    let child_code = if bit { code | (1 << shift) } else { code };
+                    heap.push((
+                        child_right - child_left,
+                        Reverse(child_code),
+                        level + 1,
+                        child_left,
+                        child_right,
+                    ));
+                }
+            }
+        }
+        result
+    }
+}
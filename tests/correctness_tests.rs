@@ -1,6 +1,49 @@
 //use crate::segment_tree::SegmentTreeBmp as SegmentTree;
+use core::cmp::Ordering;
+use core::fmt::Debug;
+use core::mem;
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+use rust_ds::AffineDelta;
+use rust_ds::AffineNode;
+use rust_ds::AndNode;
+use rust_ds::AssignDelta;
+use rust_ds::AssignNode;
+use rust_ds::AssignValue;
+use rust_ds::BeatsDelta;
+use rust_ds::BeatsNode;
+use rust_ds::Checked;
+use rust_ds::CompressedSegmentTree;
+use rust_ds::DisjointSparseTable;
+use rust_ds::Fenwick;
+use rust_ds::Fenwick2D;
+use rust_ds::FenwickRangeAdd;
+use rust_ds::FenwickRangeAddSum;
+use rust_ds::GcdNode;
+use rust_ds::LiChaoTree;
+use rust_ds::LiChaoTreeSparse;
+use rust_ds::Line;
+use rust_ds::MaxNode;
+use rust_ds::MaxSubarrayNode;
+use rust_ds::MaxSubarrayValue;
+use rust_ds::MinNode;
 use rust_ds::SegmentTree;
+use rust_ds::SegmentTree2D;
+use rust_ds::SegmentTreeBmp;
+use rust_ds::SegmentTreeBottomUp;
+use rust_ds::SegmentTreeError;
+use rust_ds::SegmentTreeFn;
+use rust_ds::SegmentTreePersistent;
+use rust_ds::SegmentTreeSparse;
+use rust_ds::SegmentTreeState;
+use rust_ds::SparseTable;
 use rust_ds::SumNode;
+use rust_ds::SumValue;
+use rust_ds::Treap;
+use rust_ds::WaveletMatrix;
+use std::num::Saturating;
+use std::num::Wrapping;
 
 enum Operation {
     Update {
@@ -46,3 +89,1478 @@ fn segment_tree() {
         }
     }
 }
+
+struct Row {
+    label: String,
+    weight: i64,
+}
+
+// Aggregates rows into the labels of the heaviest rows, exercising non-Copy leaves
+#[derive(Clone)]
+struct HeaviestNode {
+    heaviest: (i64, Vec<String>),
+    delta: i64,
+}
+
+impl SegmentTreeState for HeaviestNode {
+    type ArrayT = Row;
+    type LeafT = (i64, Vec<String>);
+    type DeltaT = i64;
+
+    fn reduce(a: Self::LeafT, b: Self::LeafT) -> Self::LeafT {
+        match a.0.cmp(&b.0) {
+            Ordering::Greater => a,
+            Ordering::Less => b,
+            Ordering::Equal => (a.0, [a.1, b.1].concat()),
+        }
+    }
+
+    fn identity() -> Self::LeafT {
+        (i64::MIN, vec![])
+    }
+
+    fn compose_delta(delta: &Self::DeltaT, next_delta: &Self::DeltaT) -> Self::DeltaT {
+        delta + next_delta
+    }
+
+    fn apply_delta_to_value(value: Self::LeafT, delta: &Self::DeltaT, _len: usize) -> Self::LeafT {
+        (value.0 + delta, value.1)
+    }
+
+    fn new() -> Self {
+        Self {
+            heaviest: (i64::MIN, vec![]),
+            delta: 0,
+        }
+    }
+
+    fn new_leaf(element: &Self::ArrayT, _leaf_pos: usize) -> Self {
+        Self {
+            heaviest: (element.weight, vec![element.label.clone()]),
+            delta: 0,
+        }
+    }
+
+    fn new_with_children(left_child: &Self, right_child: &Self) -> Self {
+        Self {
+            heaviest: Self::reduce(left_child.get_value(), right_child.get_value()),
+            delta: 0,
+        }
+    }
+
+    fn get_delta(&self) -> Self::DeltaT {
+        self.delta
+    }
+
+    fn update_delta(&mut self, delta: &Self::DeltaT) {
+        self.delta += delta;
+    }
+
+    fn get_value(&self) -> Self::LeafT {
+        self.heaviest.clone()
+    }
+
+    fn update_value(&mut self, left_child: &Self, right_child: &Self) {
+        self.heaviest = Self::reduce(left_child.get_value(), right_child.get_value());
+    }
+
+    fn push(&mut self, left_child: &mut Self, right_child: &mut Self, _len: usize) {
+        self.heaviest.0 += self.delta;
+        left_child.delta += self.delta;
+        right_child.delta += self.delta;
+        self.delta = 0;
+    }
+
+    fn push_leaf(&mut self) {
+        self.heaviest.0 += self.delta;
+        self.delta = 0;
+    }
+}
+
+#[test]
+fn segment_tree_non_copy_leaves() {
+    let rows: Vec<Row> = [("a", 3), ("b", 7), ("c", 1), ("d", 7), ("e", 2)]
+        .into_iter()
+        .map(|(label, weight)| Row {
+            label: label.to_string(),
+            weight,
+        })
+        .collect();
+
+    let mut st: SegmentTree<HeaviestNode> = SegmentTree::new(&rows);
+    let mut st_bmp: SegmentTreeBmp<HeaviestNode> = SegmentTreeBmp::new(&rows);
+
+    let expected = (7, vec!["b".to_string(), "d".to_string()]);
+    assert_eq!(st.query(0, 4), expected);
+    assert_eq!(st_bmp.query(0, 4), expected);
+
+    st.update(2, 4, &6);
+    st_bmp.update(2, 4, &6);
+
+    let expected = (13, vec!["d".to_string()]);
+    assert_eq!(st.query(0, 4), expected);
+    assert_eq!(st_bmp.query(0, 4), expected);
+    let expected = (7, vec!["b".to_string(), "c".to_string()]);
+    assert_eq!(st.query(0, 2), expected);
+    assert_eq!(st_bmp.query(0, 2), expected);
+}
+
+fn check_sum_node<T: SumValue + PartialEq + Debug>(gen: impl Fn(&mut StdRng) -> T) {
+    let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+    let n = 50;
+    let mut arr: Vec<T> = (0..n).map(|_| gen(&mut rng)).collect();
+
+    let mut st: SegmentTree<SumNode<T>> = SegmentTree::new(&arr);
+    let mut st_bmp: SegmentTreeBmp<SumNode<T>> = SegmentTreeBmp::new(&arr);
+    let mut st_bottom_up: SegmentTreeBottomUp<SumNode<T>> = SegmentTreeBottomUp::new(&arr);
+
+    for _ in 0..2000 {
+        let left = rng.gen_range(0..n);
+        let right = rng.gen_range(left..n);
+        if rng.gen_bool(0.5) {
+            let delta = gen(&mut rng);
+            arr[left..=right]
+                .iter_mut()
+                .for_each(|x| *x = x.plus(delta));
+            st.update(left, right, &delta);
+            st_bmp.update(left, right, &delta);
+            st_bottom_up.update(left, right, &delta);
+        } else {
+            let sum = arr[left..=right].iter().fold(T::zero(), |a, b| a.plus(*b));
+            assert_eq!(st.query(left, right), sum);
+            assert_eq!(st_bmp.query(left, right), sum);
+            assert_eq!(st_bottom_up.query(left, right), sum);
+        }
+    }
+}
+
+// Only provides zero and plus, so `times` falls back to doubling
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Mod7(u8);
+
+impl SumValue for Mod7 {
+    fn zero() -> Self {
+        Mod7(0)
+    }
+
+    fn plus(self, other: Self) -> Self {
+        Mod7((self.0 + other.0) % 7)
+    }
+}
+
+#[test]
+fn segment_tree_sum_generic() {
+    check_sum_node::<u32>(|rng| rng.gen_range(0..=1000));
+    check_sum_node::<i128>(|rng| rng.gen_range(-(1 << 100)..=(1 << 100)));
+    check_sum_node::<f64>(|rng| rng.gen_range(-100..=100) as f64 / 4.0);
+    check_sum_node::<Wrapping<u8>>(|rng| Wrapping(rng.gen()));
+    check_sum_node::<Mod7>(|rng| Mod7(rng.gen_range(0..7)));
+}
+
+#[test]
+fn segment_tree_sum_overflow() {
+    let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+    let n = 50;
+    let mut arr: Vec<i128> = (0..n)
+        .map(|_| rng.gen_range(0..=i64::MAX / 20) as i128)
+        .collect();
+
+    let mut st_checked: SegmentTree<SumNode<Checked<i64>>> = SegmentTree::new(
+        &arr.iter()
+            .map(|x| Checked::new(*x as i64))
+            .collect::<Vec<_>>(),
+    );
+    let mut st_saturating: SegmentTreeBmp<SumNode<Saturating<i64>>> = SegmentTreeBmp::new(
+        &arr.iter()
+            .map(|x| Saturating(*x as i64))
+            .collect::<Vec<_>>(),
+    );
+    let mut st_wrapping: SegmentTree<SumNode<Wrapping<i64>>> =
+        SegmentTree::new(&arr.iter().map(|x| Wrapping(*x as i64)).collect::<Vec<_>>());
+
+    for _ in 0..2000 {
+        let left = rng.gen_range(0..n);
+        let right = rng.gen_range(left..n);
+        if rng.gen_bool(0.5) {
+            let delta = rng.gen_range(0..=i64::MAX / 200);
+            arr[left..=right]
+                .iter_mut()
+                .for_each(|x| *x += delta as i128);
+            st_checked.update(left, right, &Checked::new(delta));
+            st_saturating.update(left, right, &Saturating(delta));
+            st_wrapping.update(left, right, &Wrapping(delta));
+        } else {
+            let sum: i128 = arr[left..=right].iter().sum();
+            let expected = i64::try_from(sum).ok();
+            assert_eq!(st_checked.query(left, right).get(), expected);
+            assert_eq!(
+                st_saturating.query(left, right).0,
+                expected.unwrap_or(i64::MAX)
+            );
+            assert_eq!(st_wrapping.query(left, right).0, sum as i64);
+        }
+    }
+
+    // Overflowing elements only poison the nodes above them
+    let mut st: SegmentTree<SumNode<Checked<i64>>> =
+        SegmentTree::new(&[Checked::new(i64::MAX), Checked::new(1), Checked::new(2)]);
+    assert_eq!(st.query(0, 2).get(), None);
+    assert_eq!(st.query(1, 2).get(), Some(3));
+    st.update(0, 0, &Checked::new(1));
+    assert_eq!(st.query(0, 0).get(), None);
+    st.set(0, &Checked::new(5));
+    assert_eq!(st.query(0, 2).get(), Some(8));
+
+    // Lengths past the range of a narrow type are only truncated by Wrapping
+    let mut st: SegmentTree<SumNode<u8>> = SegmentTree::new(&[0; 256]);
+    st.update(0, 99, &2);
+    assert_eq!(st.query(0, 255), 200);
+    let mut st_wrapping: SegmentTree<SumNode<Wrapping<u8>>> = SegmentTree::new(&[Wrapping(0); 256]);
+    st_wrapping.update(0, 255, &Wrapping(1));
+    assert_eq!(st_wrapping.query(0, 255), Wrapping(0));
+}
+
+#[test]
+#[should_panic(expected = "length does not fit the sum type")]
+fn segment_tree_sum_length_overflow() {
+    let mut st: SegmentTree<SumNode<u8>> = SegmentTree::new(&[0; 256]);
+    st.update(0, 255, &1);
+}
+
+#[test]
+fn segment_tree_min_max() {
+    let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+    let n = 50;
+    let mut arr: Vec<i64> = (0..n).map(|_| rng.gen_range(-100..=100)).collect();
+
+    let mut st_min: SegmentTree<MinNode> = SegmentTree::new(&arr);
+    let mut st_min_bmp: SegmentTreeBmp<MinNode> = SegmentTreeBmp::new(&arr);
+    let mut st_max: SegmentTree<MaxNode> = SegmentTree::new(&arr);
+    let mut st_max_bmp: SegmentTreeBmp<MaxNode> = SegmentTreeBmp::new(&arr);
+
+    assert_eq!(st_min.query(3, 2), MinNode::identity());
+    assert_eq!(st_max_bmp.query(3, 2), MaxNode::identity());
+
+    for _ in 0..1000 {
+        let left = rng.gen_range(0..n);
+        let right = rng.gen_range(left..n);
+        if rng.gen_bool(0.5) {
+            let delta = rng.gen_range(-100..=100);
+            arr[left..=right].iter_mut().for_each(|x| *x += delta);
+            st_min.update(left, right, &delta);
+            st_min_bmp.update(left, right, &delta);
+            st_max.update(left, right, &delta);
+            st_max_bmp.update(left, right, &delta);
+        } else {
+            let min = *arr[left..=right].iter().min().unwrap();
+            let max = *arr[left..=right].iter().max().unwrap();
+            let min_pos = left + arr[left..=right].iter().position(|x| *x == min).unwrap();
+            let max_pos = left + arr[left..=right].iter().position(|x| *x == max).unwrap();
+            assert_eq!(st_min.query(left, right), (min, min_pos));
+            assert_eq!(st_min_bmp.query(left, right), (min, min_pos));
+            assert_eq!(st_max.query(left, right), (max, max_pos));
+            assert_eq!(st_max_bmp.query(left, right), (max, max_pos));
+        }
+    }
+}
+
+#[test]
+fn segment_tree_assign() {
+    let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+    let n = 50;
+    let mut arr: Vec<i64> = (0..n).map(|_| rng.gen_range(-100..=100)).collect();
+
+    let mut st: SegmentTree<AssignNode> = SegmentTree::new(&arr);
+    let mut st_bmp: SegmentTreeBmp<AssignNode> = SegmentTreeBmp::new(&arr);
+
+    for _ in 0..2000 {
+        let left = rng.gen_range(0..n);
+        let right = rng.gen_range(left..n);
+        match rng.gen_range(0..3) {
+            0 => {
+                let value = rng.gen_range(-100..=100);
+                arr[left..=right].iter_mut().for_each(|x| *x = value);
+                st.update(left, right, &AssignDelta::set(value));
+                st_bmp.update(left, right, &AssignDelta::set(value));
+            }
+            1 => {
+                let delta = rng.gen_range(-100..=100);
+                arr[left..=right].iter_mut().for_each(|x| *x += delta);
+                st.update(left, right, &AssignDelta::add(delta));
+                st_bmp.update(left, right, &AssignDelta::add(delta));
+            }
+            _ => {
+                let expected = AssignValue {
+                    sum: arr[left..=right].iter().sum(),
+                    min: *arr[left..=right].iter().min().unwrap(),
+                    max: *arr[left..=right].iter().max().unwrap(),
+                };
+                assert_eq!(st.query(left, right), expected);
+                assert_eq!(st_bmp.query(left, right), expected);
+            }
+        }
+    }
+}
+
+#[test]
+fn segment_tree_max_subarray() {
+    let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+    let n = 50;
+    let mut arr: Vec<i64> = (0..n).map(|_| rng.gen_range(-100..=100)).collect();
+
+    let mut st: SegmentTree<MaxSubarrayNode> = SegmentTree::new(&arr);
+    let mut st_bmp: SegmentTreeBmp<MaxSubarrayNode> = SegmentTreeBmp::new(&arr);
+
+    for _ in 0..2000 {
+        let left = rng.gen_range(0..n);
+        let right = rng.gen_range(left..n);
+        if rng.gen_bool(0.5) {
+            let value = rng.gen_range(-100..=100);
+            arr[left..=right].iter_mut().for_each(|x| *x = value);
+            st.update(left, right, &Some(value));
+            st_bmp.update(left, right, &Some(value));
+        } else {
+            let range = &arr[left..=right];
+            let prefix_sums: Vec<i64> = (0..=range.len())
+                .map(|len| range[..len].iter().sum())
+                .collect();
+            let mut expected = MaxSubarrayValue {
+                sum: prefix_sums[range.len()],
+                prefix: i64::MIN,
+                suffix: i64::MIN,
+                best: i64::MIN,
+            };
+            for i in 0..range.len() {
+                for j in i + 1..=range.len() {
+                    let sum = prefix_sums[j] - prefix_sums[i];
+                    expected.best = expected.best.max(sum);
+                    if i == 0 {
+                        expected.prefix = expected.prefix.max(sum);
+                    }
+                    if j == range.len() {
+                        expected.suffix = expected.suffix.max(sum);
+                    }
+                }
+            }
+            assert_eq!(st.query(left, right), expected);
+            assert_eq!(st_bmp.query(left, right), expected);
+        }
+    }
+}
+
+fn check_affine<const MOD: u64>(seed: u64) {
+    let mut rng: StdRng = SeedableRng::seed_from_u64(seed);
+    let n = 50;
+    let mut arr: Vec<u64> = (0..n).map(|_| rng.gen_range(0..MOD)).collect();
+
+    let mut st: SegmentTree<AffineNode<MOD>> = SegmentTree::new(&arr);
+    let mut st_bmp: SegmentTreeBmp<AffineNode<MOD>> = SegmentTreeBmp::new(&arr);
+
+    for _ in 0..2000 {
+        let left = rng.gen_range(0..n);
+        let right = rng.gen_range(left..n);
+        if rng.gen_bool(0.5) {
+            let delta = AffineDelta::new(rng.gen_range(0..MOD), rng.gen_range(0..MOD));
+            arr[left..=right].iter_mut().for_each(|x| {
+                *x = ((*x as u128 * delta.mul as u128 + delta.add as u128) % MOD as u128) as u64
+            });
+            st.update(left, right, &delta);
+            st_bmp.update(left, right, &delta);
+        } else {
+            let expected = arr[left..=right].iter().fold(0, |acc, x| (acc + x) % MOD);
+            assert_eq!(st.query(left, right), expected);
+            assert_eq!(st_bmp.query(left, right), expected);
+        }
+    }
+}
+
+#[test]
+fn segment_tree_affine() {
+    check_affine::<7>(0);
+    check_affine::<998_244_353>(1);
+}
+
+#[test]
+fn segment_tree_persistent() {
+    let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+    let n = 50;
+    let arr: Vec<i64> = (0..n).map(|_| rng.gen_range(-100..=100)).collect();
+
+    let mut st: SegmentTreePersistent<SumNode> = SegmentTreePersistent::new(&arr);
+    let mut st_min: SegmentTreePersistent<MinNode> = SegmentTreePersistent::new(&arr);
+    let mut history: Vec<Vec<i64>> = vec![arr];
+
+    for _ in 0..2000 {
+        let version = rng.gen_range(0..history.len());
+        let left = rng.gen_range(0..n);
+        let right = rng.gen_range(left..n);
+        if rng.gen_bool(0.5) {
+            let delta = rng.gen_range(-100..=100);
+            let mut arr = history[version].clone();
+            arr[left..=right].iter_mut().for_each(|x| *x += delta);
+            history.push(arr);
+            assert_eq!(st.update(version, left, right, &delta), history.len() - 1);
+            assert_eq!(
+                st_min.update(version, left, right, &delta),
+                history.len() - 1
+            );
+        } else {
+            let arr = &history[version];
+            let min = *arr[left..=right].iter().min().unwrap();
+            let min_pos = left + arr[left..=right].iter().position(|x| *x == min).unwrap();
+            assert_eq!(
+                st.query(version, left, right),
+                arr[left..=right].iter().sum::<i64>()
+            );
+            assert_eq!(st_min.query(version, left, right), (min, min_pos));
+        }
+    }
+    assert_eq!(st.num_versions(), history.len());
+}
+
+#[test]
+fn segment_tree_sparse() {
+    let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+    let n = 50;
+    let lo: i64 = -25;
+    let mut arr: Vec<i64> = vec![5; n];
+
+    let mut st: SegmentTreeSparse<SumNode> = SegmentTreeSparse::new(lo, lo + n as i64 - 1, 5);
+    let mut st_min: SegmentTreeSparse<MinNode> = SegmentTreeSparse::new(lo, lo + n as i64 - 1, 5);
+
+    for _ in 0..2000 {
+        let left = rng.gen_range(0..n);
+        let right = rng.gen_range(left..n);
+        let (x_left, x_right) = (lo + left as i64, lo + right as i64);
+        if rng.gen_bool(0.5) {
+            let delta = rng.gen_range(-100..=100);
+            arr[left..=right].iter_mut().for_each(|x| *x += delta);
+            st.update(x_left, x_right, &delta);
+            st_min.update(x_left, x_right, &delta);
+        } else {
+            // Positions seen by the nodes are offsets from `lo`
+            let min = *arr[left..=right].iter().min().unwrap();
+            let min_pos = left + arr[left..=right].iter().position(|x| *x == min).unwrap();
+            assert_eq!(
+                st.query(x_left, x_right),
+                arr[left..=right].iter().sum::<i64>()
+            );
+            assert_eq!(st_min.query(x_left, x_right), (min, min_pos));
+        }
+    }
+    // Ranges are clamped to the domain
+    assert_eq!(st.query(i64::MIN, i64::MAX), arr.iter().sum::<i64>());
+    assert_eq!(st.query(100, 200), 0);
+
+    // Splitting keeps pending range deltas on both sides, moved positions go back to 5
+    let mut upper = st.split_at(lo + 20);
+    assert_eq!(st.query(lo, lo + 19), arr[..20].iter().sum::<i64>());
+    assert_eq!(st.query(lo + 20, i64::MAX), 5 * (n as i64 - 20));
+    assert_eq!(
+        upper.query(lo + 20, i64::MAX),
+        arr[20..].iter().sum::<i64>()
+    );
+    assert_eq!(upper.query(lo, lo + 19), 5 * 20);
+
+    let bound = 1_000_000_000_000_000_000;
+    let mut st: SegmentTreeSparse<SumNode> = SegmentTreeSparse::new(-bound, bound, 0);
+    st.update(-bound + 10, bound, &1);
+    st.update(-1, 1, &5);
+    assert_eq!(st.query(-bound, bound), 2 * bound - 9 + 15);
+    assert_eq!(st.query(-bound, -bound + 11), 2);
+    assert_eq!(st.query(0, bound), bound + 1 + 10);
+
+    let mut st: SegmentTreeSparse<SumNode> = SegmentTreeSparse::new(i64::MIN, i64::MAX - 1, 0);
+    st.update(-5, 5, &1);
+    assert_eq!(st.query(i64::MIN, i64::MAX), 11);
+    assert_eq!(
+        SegmentTreeSparse::<SumNode>::try_new(5, 4, 0).err(),
+        Some(SegmentTreeError::EmptyInput)
+    );
+    assert_eq!(
+        SegmentTreeSparse::<SumNode>::try_new(i64::MIN, i64::MAX, 0).err(),
+        Some(SegmentTreeError::DomainTooLarge {
+            lo: i64::MIN,
+            hi: i64::MAX
+        })
+    );
+}
+
+#[test]
+fn segment_tree_sparse_merge_split() {
+    let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+    let n = 50;
+    let hi = n as i64 - 1;
+    let mut arrs: Vec<Vec<i64>> = vec![vec![0; n]; 4];
+    let mut trees: Vec<SegmentTreeSparse<SumNode>> =
+        (0..4).map(|_| SegmentTreeSparse::new(0, hi, 0)).collect();
+    // 0 is neutral for gcd, so untouched positions can be skipped as well
+    let mut arrs_gcd: Vec<Vec<u64>> = vec![vec![0; n]; 4];
+    let mut trees_gcd: Vec<SegmentTreeSparse<GcdNode>> =
+        (0..4).map(|_| SegmentTreeSparse::new(0, hi, 0)).collect();
+
+    for _ in 0..2000 {
+        let i = rng.gen_range(0..arrs.len());
+        match rng.gen_range(0..4) {
+            0 => {
+                // Mostly single positions, range updates leave deltas on childless nodes
+                let left = rng.gen_range(0..n);
+                let right = if rng.gen_bool(0.5) {
+                    left
+                } else {
+                    rng.gen_range(left..n)
+                };
+                let delta = rng.gen_range(-100..=100);
+                arrs[i][left..=right].iter_mut().for_each(|x| *x += delta);
+                trees[i].update(left as i64, right as i64, &delta);
+                let value = rng.gen_range(1..=6) * rng.gen_range(1..=30);
+                arrs_gcd[i][left..=right].fill(value);
+                trees_gcd[i].update(left as i64, right as i64, &Some(value));
+            }
+            1 => {
+                let j = rng.gen_range(0..arrs.len());
+                if i != j {
+                    let tree = mem::replace(&mut trees[j], SegmentTreeSparse::new(0, hi, 0));
+                    trees[i].merge(tree);
+                    let merged = mem::replace(&mut arrs[j], vec![0; n]);
+                    arrs[i].iter_mut().zip(merged).for_each(|(x, y)| *x += y);
+
+                    let tree = mem::replace(&mut trees_gcd[j], SegmentTreeSparse::new(0, hi, 0));
+                    trees_gcd[i].merge(tree);
+                    let merged = mem::replace(&mut arrs_gcd[j], vec![0; n]);
+                    arrs_gcd[i]
+                        .iter_mut()
+                        .zip(merged)
+                        .for_each(|(x, y)| *x = gcd(*x, y));
+                }
+            }
+            2 => {
+                let j = rng.gen_range(0..arrs.len());
+                if i != j {
+                    let pos = rng.gen_range(0..=n);
+                    trees[j] = trees[i].split_at(pos as i64);
+                    let mut moved = vec![0; n];
+                    moved[pos..].copy_from_slice(&arrs[i][pos..]);
+                    arrs[i][pos..].fill(0);
+                    arrs[j] = moved;
+
+                    trees_gcd[j] = trees_gcd[i].split_at(pos as i64);
+                    let mut moved = vec![0; n];
+                    moved[pos..].copy_from_slice(&arrs_gcd[i][pos..]);
+                    arrs_gcd[i][pos..].fill(0);
+                    arrs_gcd[j] = moved;
+                }
+            }
+            _ => {
+                let left = rng.gen_range(0..n);
+                let right = rng.gen_range(left..n);
+                assert_eq!(
+                    trees[i].query(left as i64, right as i64),
+                    arrs[i][left..=right].iter().sum::<i64>()
+                );
+                assert_eq!(
+                    trees_gcd[i].query(left as i64, right as i64),
+                    arrs_gcd[i][left..=right].iter().fold(0, |a, b| gcd(a, *b))
+                );
+            }
+        }
+    }
+
+    // The range update leaves its delta on childless nodes of `a`, which must not be skipped
+    let mut a: SegmentTreeSparse<SumNode> = SegmentTreeSparse::new(0, 7, 0);
+    let mut b: SegmentTreeSparse<SumNode> = SegmentTreeSparse::new(0, 7, 0);
+    a.update(1, 1, &1);
+    a.update(0, 7, &5);
+    b.update(6, 6, &1);
+    b.merge(a);
+    assert_eq!(b.query(0, 7), 42);
+    assert_eq!(b.query(6, 6), 6);
+    assert_eq!(b.query(4, 5), 10);
+}
+
+#[test]
+fn segment_tree_compressed() {
+    let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+    let n = 50;
+    let mut keys: Vec<i64> = Vec::new();
+    while keys.len() < n {
+        let key = rng.gen_range(-1_000_000_000_000..=1_000_000_000_000);
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+    let arr: Vec<i64> = (0..n).map(|_| rng.gen_range(-100..=100)).collect();
+    let mut entries: Vec<(i64, i64)> = keys.iter().copied().zip(arr.iter().copied()).collect();
+    entries.sort();
+
+    let mut st: CompressedSegmentTree<i64, SumNode> = CompressedSegmentTree::new(&keys, &arr);
+    let mut st_min: CompressedSegmentTree<i64, MinNode> = CompressedSegmentTree::new(&keys, &arr);
+    assert_eq!(st.len(), n);
+    assert!(st.keys().windows(2).all(|pair| pair[0] < pair[1]));
+
+    for _ in 0..2000 {
+        // Bounds are either table keys or arbitrary values in between
+        let mut bound = || {
+            if rng.gen_bool(0.5) {
+                keys[rng.gen_range(0..n)]
+            } else {
+                rng.gen_range(-1_100_000_000_000..=1_100_000_000_000)
+            }
+        };
+        let (key_lo, key_hi) = (bound(), bound());
+        let range: Vec<usize> = (0..n)
+            .filter(|i| key_lo <= entries[*i].0 && entries[*i].0 <= key_hi)
+            .collect();
+
+        match rng.gen_range(0..3) {
+            0 => {
+                let delta = rng.gen_range(-100..=100);
+                range.iter().for_each(|i| entries[*i].1 += delta);
+                st.update(&key_lo, &key_hi, &delta);
+                st_min.update(&key_lo, &key_hi, &delta);
+            }
+            1 => {
+                let sum: i64 = range.iter().map(|i| entries[*i].1).sum();
+                assert_eq!(st.query(&key_lo, &key_hi), sum);
+                let min = range.iter().map(|i| (entries[*i].1, *i)).min();
+                assert_eq!(
+                    st_min.query(&key_lo, &key_hi),
+                    min.unwrap_or(MinNode::identity())
+                );
+            }
+            _ => {
+                let expected = entries.iter().find(|entry| entry.0 == key_lo);
+                assert_eq!(st.get(&key_lo), expected.map(|entry| entry.1));
+            }
+        }
+    }
+}
+
+#[test]
+fn segment_tree_2d() {
+    let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+    let (rows, cols) = (12, 9);
+    let mut grid: Vec<Vec<i64>> = (0..rows)
+        .map(|_| (0..cols).map(|_| rng.gen_range(-100..=100)).collect())
+        .collect();
+
+    let mut st: SegmentTree2D<SumNode> = SegmentTree2D::new(&grid);
+    let mut st_min: SegmentTree2D<MinNode> = SegmentTree2D::new(&grid);
+    assert_eq!((st.rows(), st.cols()), (rows, cols));
+
+    for _ in 0..2000 {
+        let (row1, col1) = (rng.gen_range(0..rows), rng.gen_range(0..cols));
+        let (row2, col2) = (rng.gen_range(row1..rows), rng.gen_range(col1..cols));
+        match rng.gen_range(0..4) {
+            0 => {
+                let delta = rng.gen_range(-100..=100);
+                grid[row1..=row2]
+                    .iter_mut()
+                    .for_each(|row| row[col1..=col2].iter_mut().for_each(|x| *x += delta));
+                st.update(row1, col1, row2, col2, &delta);
+                // MinNode is not AdditiveDelta, so its rectangles are updated cell by cell
+                for row in row1..=row2 {
+                    for col in col1..=col2 {
+                        st_min.update_point(row, col, &delta);
+                    }
+                }
+            }
+            1 => {
+                let delta = rng.gen_range(-100..=100);
+                grid[row1][col1] += delta;
+                st.update_point(row1, col1, &delta);
+                st_min.update_point(row1, col1, &delta);
+            }
+            _ => {
+                let cells: Vec<(i64, usize)> = grid[row1..=row2]
+                    .iter()
+                    .flat_map(|row| (col1..=col2).map(move |col| (row[col], col)))
+                    .collect();
+                assert_eq!(
+                    st.query(row1, col1, row2, col2),
+                    cells.iter().map(|cell| cell.0).sum::<i64>()
+                );
+                assert_eq!(
+                    st_min.query(row1, col1, row2, col2),
+                    *cells.iter().min().unwrap()
+                );
+            }
+        }
+    }
+
+    let empty: Vec<Vec<i64>> = vec![];
+    assert_eq!(
+        SegmentTree2D::<SumNode>::try_new(&empty).err(),
+        Some(SegmentTreeError::EmptyInput)
+    );
+    assert_eq!(
+        SegmentTree2D::<SumNode>::try_new(&[vec![], vec![]]).err(),
+        Some(SegmentTreeError::EmptyInput)
+    );
+    assert_eq!(
+        SegmentTree2D::<SumNode>::try_new(&[vec![1, 2], vec![3, 4], vec![5]]).err(),
+        Some(SegmentTreeError::RaggedGrid {
+            row: 2,
+            len: 1,
+            expected: 2
+        })
+    );
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[test]
+fn segment_tree_fn() {
+    let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+    let n = 50;
+    let mut arr: Vec<u64> = (0..n).map(|_| rng.gen_range(1..=1000)).collect();
+    let mut arr_sum = arr.clone();
+
+    // xor and gcd with range set
+    let set = |delta: &Option<u64>, next_delta: &Option<u64>| next_delta.or(*delta);
+    let mut st_xor = SegmentTreeFn::new(
+        &arr,
+        0,
+        None,
+        |a: &u64, b: &u64| a ^ b,
+        |delta: &Option<u64>, value: &u64, len: usize| match delta {
+            Some(x) if len % 2 == 1 => *x,
+            Some(_) => 0,
+            None => *value,
+        },
+        set,
+    );
+    let mut st_gcd = SegmentTreeFn::new(
+        &arr,
+        0,
+        None,
+        |a: &u64, b: &u64| gcd(*a, *b),
+        |delta: &Option<u64>, value: &u64, _len: usize| delta.unwrap_or(*value),
+        set,
+    );
+    // sum with range add
+    let mut st_sum = SegmentTreeFn::new(
+        &arr_sum,
+        0,
+        0,
+        |a: &u64, b: &u64| a + b,
+        |delta: &u64, value: &u64, len: usize| value + delta * (len as u64),
+        |delta: &u64, next_delta: &u64| delta + next_delta,
+    );
+    assert_eq!(st_xor.len(), n);
+
+    for _ in 0..2000 {
+        let left = rng.gen_range(0..n);
+        let right = rng.gen_range(left..n);
+        if rng.gen_bool(0.5) {
+            let value = rng.gen_range(1..=1000);
+            arr[left..=right].iter_mut().for_each(|x| *x = value);
+            arr_sum[left..=right].iter_mut().for_each(|x| *x += value);
+            st_xor.update(left, right, &Some(value));
+            st_gcd.update(left, right, &Some(value));
+            st_sum.update(left, right, &value);
+        } else {
+            let range = &arr[left..=right];
+            assert_eq!(
+                st_xor.query(left, right),
+                range.iter().fold(0, |a, b| a ^ b)
+            );
+            assert_eq!(
+                st_gcd.query(left, right),
+                range.iter().fold(0, |a, b| gcd(a, *b))
+            );
+            assert_eq!(
+                st_sum.query(left, right),
+                arr_sum[left..=right].iter().sum::<u64>()
+            );
+        }
+    }
+
+    let out_of_bounds = SegmentTreeError::IndexOutOfBounds { index: n, len: n };
+    assert_eq!(st_sum.try_query(0, n), Err(out_of_bounds.clone()));
+    assert_eq!(st_sum.try_update(1, n, &1), Err(out_of_bounds));
+    assert_eq!(
+        st_sum.try_update(3, 2, &1),
+        Err(SegmentTreeError::InvalidRange { left: 3, right: 2 })
+    );
+    let empty: Vec<u64> = vec![];
+    let st_empty = SegmentTreeFn::try_new(
+        &empty,
+        0,
+        0,
+        |a: &u64, b: &u64| a + b,
+        |delta: &u64, value: &u64, len: usize| value + delta * (len as u64),
+        |delta: &u64, next_delta: &u64| delta + next_delta,
+    );
+    assert_eq!(st_empty.err(), Some(SegmentTreeError::EmptyInput));
+}
+
+#[test]
+fn segment_tree_gcd_and() {
+    let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+    let n = 50;
+    let mut arr: Vec<u64> = (0..n)
+        .map(|_| rng.gen_range(1..=6) * rng.gen_range(1..=30))
+        .collect();
+
+    let mut st_gcd: SegmentTreeBmp<GcdNode> = SegmentTreeBmp::new(&arr);
+    let mut st_and: SegmentTreeBmp<AndNode> = SegmentTreeBmp::new(&arr);
+
+    for _ in 0..2000 {
+        let left = rng.gen_range(0..n);
+        let right = rng.gen_range(left..n);
+        if rng.gen_bool(0.3) {
+            let value = rng.gen_range(1..=6) * rng.gen_range(1..=30);
+            arr[left..=right].iter_mut().for_each(|x| *x = value);
+            st_gcd.update(left, right, &Some(value));
+            st_and.update(left, right, &Some(value));
+        } else {
+            let range = &arr[left..=right];
+            assert_eq!(
+                st_gcd.query(left, right),
+                range.iter().fold(0, |a, b| gcd(a, *b))
+            );
+            assert_eq!(
+                st_and.query(left, right),
+                range.iter().fold(u64::MAX, |a, b| a & b)
+            );
+        }
+    }
+}
+
+#[test]
+fn segment_tree_beats() {
+    let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+    let n = 50;
+    let mut arr: Vec<i64> = (0..n).map(|_| rng.gen_range(-100..=100)).collect();
+
+    let mut st: SegmentTree<BeatsNode> = SegmentTree::new(&arr);
+    let mut st_bmp: SegmentTreeBmp<BeatsNode> = SegmentTreeBmp::new(&arr);
+    let mut st_bottom_up: SegmentTreeBottomUp<BeatsNode> = SegmentTreeBottomUp::new(&arr);
+
+    for _ in 0..5000 {
+        let left = rng.gen_range(0..n);
+        let right = rng.gen_range(left..n);
+        let x = rng.gen_range(-100..=100);
+        let delta = match rng.gen_range(0..4) {
+            0 => {
+                arr[left..=right].iter_mut().for_each(|v| *v += x);
+                BeatsDelta::add(x)
+            }
+            1 => {
+                arr[left..=right].iter_mut().for_each(|v| *v = (*v).min(x));
+                BeatsDelta::chmin(x)
+            }
+            2 => {
+                arr[left..=right].iter_mut().for_each(|v| *v = (*v).max(x));
+                BeatsDelta::chmax(x)
+            }
+            _ => {
+                let value = st.query(left, right);
+                assert_eq!(value, st_bmp.query(left, right));
+                assert_eq!(value, st_bottom_up.query(left, right));
+                assert_eq!(value.sum, arr[left..=right].iter().sum::<i64>());
+                assert_eq!(value.min, *arr[left..=right].iter().min().unwrap());
+                assert_eq!(value.max, *arr[left..=right].iter().max().unwrap());
+                continue;
+            }
+        };
+        st.update(left, right, &delta);
+        st_bmp.update(left, right, &delta);
+        st_bottom_up.update(left, right, &delta);
+    }
+}
+
+#[test]
+fn segment_tree_max_right_min_left() {
+    let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+    let n = 50;
+    let mut arr: Vec<i64> = (0..n).map(|_| rng.gen_range(0..=20)).collect();
+
+    let mut st: SegmentTree<SumNode> = SegmentTree::new(&arr);
+    let mut st_bmp: SegmentTreeBmp<SumNode> = SegmentTreeBmp::new(&arr);
+
+    for _ in 0..2000 {
+        let left = rng.gen_range(0..n);
+        let right = rng.gen_range(left..n);
+        if rng.gen_bool(0.3) {
+            let delta = rng.gen_range(0..=20);
+            arr[left..=right].iter_mut().for_each(|x| *x += delta);
+            st.update(left, right, &delta);
+            st_bmp.update(left, right, &delta);
+        } else {
+            let k = rng.gen_range(0..=500);
+            let expected = (left..n)
+                .take_while(|r| arr[left..=*r].iter().sum::<i64>() <= k)
+                .last();
+            assert_eq!(st.max_right(left, |sum| *sum <= k), expected);
+            assert_eq!(st_bmp.max_right(left, |sum| *sum <= k), expected);
+
+            let expected = (0..=right)
+                .rev()
+                .take_while(|l| arr[*l..=right].iter().sum::<i64>() <= k)
+                .last();
+            assert_eq!(st.min_left(right, |sum| *sum <= k), expected);
+            assert_eq!(st_bmp.min_left(right, |sum| *sum <= k), expected);
+        }
+    }
+
+    // Nothing starts or ends past the end, even when pred always holds
+    assert_eq!(st.max_right(n, |_| true), None);
+    assert_eq!(st_bmp.max_right(n, |_| true), None);
+    assert_eq!(st.max_right(n - 1, |_| true), Some(n - 1));
+    assert_eq!(st_bmp.max_right(n - 1, |_| true), Some(n - 1));
+    assert_eq!(st.min_left(n, |_| true), None);
+    assert_eq!(st_bmp.min_left(n, |_| true), None);
+    assert_eq!(st.min_left(n - 1, |_| true), Some(0));
+    assert_eq!(st_bmp.min_left(n - 1, |_| true), Some(0));
+}
+
+#[test]
+fn segment_tree_get_set() {
+    let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+    let n = 50;
+    let mut arr: Vec<i64> = (0..n).map(|_| rng.gen_range(-100..=100)).collect();
+
+    let mut st: SegmentTree<SumNode> = SegmentTree::new(&arr);
+    let mut st_bmp: SegmentTreeBmp<SumNode> = SegmentTreeBmp::new(&arr);
+
+    for _ in 0..2000 {
+        let left = rng.gen_range(0..n);
+        let right = rng.gen_range(left..n);
+        match rng.gen_range(0..4) {
+            0 => {
+                let delta = rng.gen_range(-100..=100);
+                arr[left..=right].iter_mut().for_each(|x| *x += delta);
+                st.update(left, right, &delta);
+                st_bmp.update(left, right, &delta);
+            }
+            1 => {
+                let value = rng.gen_range(-100..=100);
+                arr[left] = value;
+                st.set(left, &value);
+                st_bmp.set(left, &value);
+            }
+            2 => {
+                assert_eq!(st.get(left), arr[left]);
+                assert_eq!(st_bmp.get(left), arr[left]);
+            }
+            _ => {
+                let sum: i64 = arr[left..=right].iter().sum();
+                assert_eq!(st.query(left, right), sum);
+                assert_eq!(st_bmp.query(left, right), sum);
+            }
+        }
+    }
+    assert_eq!(st.to_vec(), arr);
+    assert_eq!(st_bmp.to_vec(), arr);
+}
+
+#[test]
+fn segment_tree_shared_queries() {
+    let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+    let n = 50;
+    let mut arr: Vec<i64> = (0..n).map(|_| rng.gen_range(-100..=100)).collect();
+
+    let mut st: SegmentTree<AssignNode> = SegmentTree::new(&arr);
+    let mut st_bmp: SegmentTreeBmp<AssignNode> = SegmentTreeBmp::new(&arr);
+    for _ in 0..200 {
+        let left = rng.gen_range(0..n);
+        let right = rng.gen_range(left..n);
+        let value = rng.gen_range(-100..=100);
+        let delta = if rng.gen_bool(0.5) {
+            arr[left..=right].iter_mut().for_each(|x| *x = value);
+            AssignDelta::set(value)
+        } else {
+            arr[left..=right].iter_mut().for_each(|x| *x += value);
+            AssignDelta::add(value)
+        };
+        st.update(left, right, &delta);
+        st_bmp.update(left, right, &delta);
+    }
+
+    let (st, st_bmp, arr) = (&st, &st_bmp, &arr);
+    std::thread::scope(|scope| {
+        for left in 0..4 {
+            scope.spawn(move || {
+                for right in left..n {
+                    let expected = AssignValue {
+                        sum: arr[left..=right].iter().sum(),
+                        min: *arr[left..=right].iter().min().unwrap(),
+                        max: *arr[left..=right].iter().max().unwrap(),
+                    };
+                    assert_eq!(st.query(left, right), expected);
+                    assert_eq!(st_bmp.query(left, right), expected);
+                }
+            });
+        }
+    });
+}
+
+#[test]
+fn segment_tree_rollback() {
+    let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+    let n = 50;
+    let mut arr: Vec<i64> = (0..n).map(|_| rng.gen_range(-100..=100)).collect();
+
+    let mut st: SegmentTreeBmp<AssignNode> = SegmentTreeBmp::new(&arr);
+    let mut checkpoints: Vec<(usize, Vec<i64>)> = Vec::new();
+
+    for _ in 0..2000 {
+        let left = rng.gen_range(0..n);
+        let right = rng.gen_range(left..n);
+        match rng.gen_range(0..6) {
+            0 => checkpoints.push((st.checkpoint(), arr.clone())),
+            1 => {
+                if let Some((checkpoint, saved)) = checkpoints.pop() {
+                    st.rollback(checkpoint);
+                    arr = saved;
+                }
+            }
+            2 => {
+                let delta = if rng.gen_bool(0.5) {
+                    AssignDelta::set(rng.gen_range(-100..=100))
+                } else {
+                    AssignDelta::add(rng.gen_range(-100..=100))
+                };
+                arr[left..=right].iter_mut().for_each(|x| {
+                    *x = delta.assign.unwrap_or(*x) + delta.add;
+                });
+                st.update(left, right, &delta);
+            }
+            3 => {
+                arr[left] = rng.gen_range(-100..=100);
+                st.set(left, &arr[left]);
+            }
+            _ => {
+                let range = &arr[left..=right];
+                let expected = AssignValue {
+                    sum: range.iter().sum(),
+                    min: *range.iter().min().unwrap(),
+                    max: *range.iter().max().unwrap(),
+                };
+                assert_eq!(st.query(left, right), expected);
+                assert_eq!(st.get(left).sum, arr[left]);
+            }
+        }
+    }
+
+    while let Some((checkpoint, saved)) = checkpoints.pop() {
+        st.rollback(checkpoint);
+        arr = saved;
+    }
+    st.clear_checkpoints();
+    assert_eq!(
+        st.to_vec()
+            .iter()
+            .map(|value| value.sum)
+            .collect::<Vec<_>>(),
+        arr
+    );
+}
+
+#[test]
+fn segment_tree_errors() {
+    let empty: Vec<i64> = vec![];
+    assert_eq!(
+        SegmentTree::<SumNode>::try_new(&empty).err(),
+        Some(SegmentTreeError::EmptyInput)
+    );
+    assert_eq!(
+        SegmentTreeBmp::<SumNode>::try_new(&empty).err(),
+        Some(SegmentTreeError::EmptyInput)
+    );
+    assert_eq!(
+        SegmentTreePersistent::<SumNode>::try_new(&empty).err(),
+        Some(SegmentTreeError::EmptyInput)
+    );
+    assert_eq!(
+        SegmentTreeBottomUp::<SumNode>::try_new(&empty).err(),
+        Some(SegmentTreeError::EmptyInput)
+    );
+
+    let arr: Vec<i64> = vec![4, 5, 2, 1, 0];
+    let mut st: SegmentTree<SumNode> = SegmentTree::try_new(&arr).unwrap();
+    let mut st_bmp: SegmentTreeBmp<SumNode> = SegmentTreeBmp::try_new(&arr).unwrap();
+    assert_eq!(st.len(), 5);
+    assert_eq!(st_bmp.len(), 5);
+    assert_eq!(SegmentTreeBottomUp::<SumNode>::new(&arr).len(), 5);
+    assert!(!st.is_empty() && !st_bmp.is_empty());
+
+    let invalid_range = SegmentTreeError::InvalidRange { left: 3, right: 2 };
+    let out_of_bounds = SegmentTreeError::IndexOutOfBounds { index: 5, len: 5 };
+    assert_eq!(st.try_query(3, 2), Err(invalid_range.clone()));
+    assert_eq!(st_bmp.try_query(3, 2), Err(invalid_range.clone()));
+    assert_eq!(st.try_update(3, 2, &1), Err(invalid_range.clone()));
+    assert_eq!(st_bmp.try_update(3, 2, &1), Err(invalid_range));
+    assert_eq!(st.try_query(0, 5), Err(out_of_bounds.clone()));
+    assert_eq!(st_bmp.try_query(0, 5), Err(out_of_bounds.clone()));
+    assert_eq!(st.try_update(1, 5, &1), Err(out_of_bounds.clone()));
+    assert_eq!(st_bmp.try_update(1, 5, &1), Err(out_of_bounds.clone()));
+    assert_eq!(st.try_get(5), Err(out_of_bounds.clone()));
+    assert_eq!(st_bmp.try_get(5), Err(out_of_bounds.clone()));
+    assert_eq!(st.try_set(5, &50), Err(out_of_bounds.clone()));
+    assert_eq!(st_bmp.try_set(5, &50), Err(out_of_bounds));
+
+    assert_eq!(st.try_update(1, 4, &1), Ok(()));
+    assert_eq!(st_bmp.try_update(1, 4, &1), Ok(()));
+    assert_eq!(st.try_query(0, 4), Ok(16));
+    assert_eq!(st_bmp.try_query(0, 4), Ok(16));
+    assert_eq!(st.try_set(4, &50), Ok(()));
+    assert_eq!(st_bmp.try_set(4, &50), Ok(()));
+    assert_eq!(st.try_get(4), Ok(50));
+    assert_eq!(st_bmp.try_get(4), Ok(50));
+}
+
+// An out of bounds position must not land on the last leaf
+#[test]
+#[should_panic(expected = "IndexOutOfBounds { index: 100, len: 3 }")]
+fn segment_tree_set_out_of_bounds() {
+    let mut st: SegmentTree<SumNode> = SegmentTree::new(&[1, 2, 3]);
+    st.set(100, &50);
+}
+
+#[test]
+#[should_panic(expected = "IndexOutOfBounds { index: 7, len: 3 }")]
+fn segment_tree_bmp_get_out_of_bounds() {
+    let mut st_bmp: SegmentTreeBmp<SumNode> = SegmentTreeBmp::new(&[1, 2, 3]);
+    st_bmp.get(7);
+}
+
+#[test]
+fn fenwick() {
+    let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+    let n = 50;
+    let mut arr: Vec<i64> = (0..n).map(|_| rng.gen_range(0..=100)).collect();
+
+    let mut fenwick: Fenwick<i64> = Fenwick::new(&arr);
+    let mut fenwick_range_add: FenwickRangeAdd<i64> = FenwickRangeAdd::new(&arr);
+    let mut fenwick_range_add_sum: FenwickRangeAddSum<i64> = FenwickRangeAddSum::new(&arr);
+
+    for _ in 0..2000 {
+        let left = rng.gen_range(0..n);
+        let right = rng.gen_range(left..n);
+        match rng.gen_range(0..4) {
+            0 => {
+                let delta = rng.gen_range(0..=100);
+                arr[left] += delta;
+                fenwick.add(left, delta);
+                fenwick_range_add.add(left, left, delta);
+                fenwick_range_add_sum.add(left, left, delta);
+            }
+            1 => {
+                let delta = rng.gen_range(0..=100);
+                arr[left..=right].iter_mut().for_each(|x| *x += delta);
+                (left..=right).for_each(|pos| fenwick.add(pos, delta));
+                fenwick_range_add.add(left, right, delta);
+                fenwick_range_add_sum.add(left, right, delta);
+            }
+            2 => {
+                let prefix = rng.gen_range(0..=arr.iter().sum::<i64>() + 10);
+                let expected = (0..n)
+                    .find(|pos| arr[..=*pos].iter().sum::<i64>() >= prefix)
+                    .unwrap_or(n);
+                assert_eq!(fenwick.lower_bound(prefix), expected);
+            }
+            _ => {
+                let sum: i64 = arr[left..=right].iter().sum();
+                assert_eq!(fenwick.range_sum(left, right), sum);
+                assert_eq!(fenwick_range_add_sum.range_sum(left, right), sum);
+                assert_eq!(fenwick_range_add.get(left), arr[left]);
+            }
+        }
+    }
+}
+
+#[test]
+fn fenwick_2d() {
+    let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+    let (rows, cols) = (12, 9);
+    let mut grid: Vec<Vec<f64>> = (0..rows)
+        .map(|_| {
+            (0..cols)
+                .map(|_| rng.gen_range(-100..=100) as f64)
+                .collect()
+        })
+        .collect();
+    let mut fenwick: Fenwick2D<f64> = Fenwick2D::from_grid(&grid);
+
+    for _ in 0..2000 {
+        let (row1, col1) = (rng.gen_range(0..rows), rng.gen_range(0..cols));
+        let (row2, col2) = (rng.gen_range(row1..rows), rng.gen_range(col1..cols));
+        if rng.gen_bool(0.5) {
+            let delta = rng.gen_range(-100..=100) as f64;
+            grid[row1][col1] += delta;
+            fenwick.add(row1, col1, delta);
+        } else {
+            let sum: f64 = grid[row1..=row2]
+                .iter()
+                .map(|row| row[col1..=col2].iter().sum::<f64>())
+                .sum();
+            assert_eq!(fenwick.rect_sum(row1, col1, row2, col2), sum);
+        }
+    }
+}
+
+#[test]
+fn wavelet_matrix() {
+    let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+    let n = 50;
+    let arr: Vec<i64> = (0..n).map(|_| rng.gen_range(-20..=20)).collect();
+    let wm = WaveletMatrix::new(&arr);
+    assert_eq!(wm.len(), n);
+
+    for _ in 0..2000 {
+        let left = rng.gen_range(0..n);
+        let right = rng.gen_range(left..n);
+        let mut sorted = arr[left..=right].to_vec();
+        sorted.sort();
+
+        let k = rng.gen_range(0..=sorted.len());
+        assert_eq!(wm.kth_smallest(left, right, k), sorted.get(k).copied());
+
+        let value = rng.gen_range(-25..=25);
+        assert_eq!(
+            wm.rank(left, right, &value),
+            sorted.iter().filter(|x| **x < value).count()
+        );
+
+        let lo = rng.gen_range(-25..=25);
+        let hi = rng.gen_range(-25..=25);
+        assert_eq!(
+            wm.count_range(left, right, &lo, &hi),
+            sorted.iter().filter(|x| lo <= **x && **x <= hi).count()
+        );
+
+        let mut counts: Vec<(i64, usize)> = Vec::new();
+        for x in sorted.iter() {
+            match counts.last_mut() {
+                Some((value, count)) if value == x => *count += 1,
+                _ => counts.push((*x, 1)),
+            }
+        }
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        counts.truncate(k);
+        assert_eq!(wm.top_k(left, right, k), counts);
+    }
+
+    let words = ["pear", "apple", "fig", "apple", "kiwi", "fig", "apple"];
+    let wm = WaveletMatrix::new(&words);
+    assert_eq!(wm.kth_smallest(1, 5, 2), Some("fig"));
+    assert_eq!(wm.top_k(0, 6, 2), vec![("apple", 3), ("fig", 2)]);
+}
+
+// Padding bits of the last block must not be counted as elements
+#[test]
+#[should_panic(expected = "invalid range [0, 10] for length 3")]
+fn wavelet_matrix_rank_out_of_bounds() {
+    WaveletMatrix::new(&[5, 1, 4]).rank(0, 10, &6);
+}
+
+#[test]
+#[should_panic(expected = "invalid range [2, 1] for length 3")]
+fn wavelet_matrix_kth_smallest_inverted_range() {
+    WaveletMatrix::new(&[5, 1, 4]).kth_smallest(2, 1, 0);
+}
+
+fn max_subarray(arr: &[i64]) -> i64 {
+    (0..arr.len())
+        .flat_map(|i| (i + 1..=arr.len()).map(move |j| arr[i..j].iter().sum::<i64>()))
+        .max()
+        .unwrap()
+}
+
+#[test]
+fn treap() {
+    let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+    let mut arr: Vec<i64> = (0..50).map(|_| rng.gen_range(-100..=100)).collect();
+
+    let mut treap: Treap<SumNode> = Treap::new(&arr);
+    let mut treap_subarray: Treap<MaxSubarrayNode> = Treap::new(&arr);
+
+    for _ in 0..2000 {
+        let n = arr.len();
+        let left = rng.gen_range(0..n);
+        let right = rng.gen_range(left..n);
+        match rng.gen_range(0..7) {
+            0 => {
+                let pos = rng.gen_range(0..=n);
+                let element = rng.gen_range(-100..=100);
+                arr.insert(pos, element);
+                treap.insert(pos, &element);
+                treap_subarray.insert(pos, &element);
+            }
+            1 if n > 1 => {
+                let element = arr.remove(left);
+                assert_eq!(treap.erase(left), element);
+                assert_eq!(treap_subarray.erase(left).sum, element);
+            }
+            2 => {
+                arr[left..=right].reverse();
+                treap.reverse(left, right);
+                treap_subarray.reverse(left, right);
+            }
+            3 => {
+                let value = rng.gen_range(-100..=100);
+                arr[left..=right].iter_mut().for_each(|x| *x = value);
+                treap_subarray.update(left, right, &Some(value));
+                // SumNode can only add, rebuild its range through erase and insert
+                for pos in left..=right {
+                    treap.erase(pos);
+                    treap.insert(pos, &value);
+                }
+            }
+            4 => {
+                let delta = rng.gen_range(-100..=100);
+                arr[left..=right].iter_mut().for_each(|x| *x += delta);
+                treap.update(left, right, &delta);
+                for (pos, element) in arr.iter().enumerate().take(right + 1).skip(left) {
+                    treap_subarray.erase(pos);
+                    treap_subarray.insert(pos, element);
+                }
+            }
+            5 => {
+                // Rotate the sequence by splitting it and merging the halves the other way round
+                let (first, last) = treap.split(left);
+                treap = Treap::merge(last, first);
+                let (first, last) = treap_subarray.split(left);
+                treap_subarray = Treap::merge(last, first);
+                arr.rotate_left(left);
+            }
+            _ => {
+                assert_eq!(
+                    treap.query(left, right),
+                    arr[left..=right].iter().sum::<i64>()
+                );
+                assert_eq!(
+                    treap_subarray.query(left, right).best,
+                    max_subarray(&arr[left..=right])
+                );
+                assert_eq!(treap.get(left), arr[left]);
+            }
+        }
+        assert_eq!(treap.len(), arr.len());
+    }
+    assert_eq!(treap.to_vec(), arr);
+}
+
+#[test]
+fn li_chao_tree() {
+    let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+    let xs: Vec<i64> = (0..50).map(|_| rng.gen_range(-1000..=1000)).collect();
+    let mut lines: Vec<(Line, i64, i64)> = Vec::new();
+
+    let mut tree_min = LiChaoTree::new_min(&xs);
+    let mut tree_max = LiChaoTree::new_max(&xs);
+    let mut sparse_min = LiChaoTreeSparse::new_min(-1000, 1000);
+    let mut sparse_max = LiChaoTreeSparse::new_max(-1000, 1000);
+
+    for _ in 0..2000 {
+        if rng.gen_bool(0.3) {
+            let line = Line::new(rng.gen_range(-100..=100), rng.gen_range(-10000..=10000));
+            if rng.gen_bool(0.5) {
+                lines.push((line, i64::MIN, i64::MAX));
+                tree_min.add_line(line);
+                tree_max.add_line(line);
+                sparse_min.add_line(line);
+                sparse_max.add_line(line);
+            } else {
+                let x_left = rng.gen_range(-1000..=1000);
+                let x_right = rng.gen_range(x_left..=1000);
+                lines.push((line, x_left, x_right));
+                tree_min.add_segment(line, x_left, x_right);
+                tree_max.add_segment(line, x_left, x_right);
+                sparse_min.add_segment(line, x_left, x_right);
+                sparse_max.add_segment(line, x_left, x_right);
+            }
+        } else {
+            let x = xs[rng.gen_range(0..xs.len())];
+            let values: Vec<i128> = lines
+                .iter()
+                .filter(|(_, x_left, x_right)| *x_left <= x && x <= *x_right)
+                .map(|(line, _, _)| line.eval(x))
+                .collect();
+            assert_eq!(tree_min.query(x), values.iter().min().copied());
+            assert_eq!(tree_max.query(x), values.iter().max().copied());
+            assert_eq!(sparse_min.query(x), values.iter().min().copied());
+            assert_eq!(sparse_max.query(x), values.iter().max().copied());
+        }
+    }
+
+    let mut sparse = LiChaoTreeSparse::new_min(i64::MIN, i64::MAX);
+    sparse.add_line(Line::new(1, 0));
+    sparse.add_segment(Line::new(-1, 0), 0, i64::MAX);
+    assert_eq!(sparse.query(i64::MIN), Some(i64::MIN as i128));
+    assert_eq!(sparse.query(i64::MAX), Some(-(i64::MAX as i128)));
+    assert_eq!(sparse.query(-5), Some(-5));
+
+    // Lines extend past the domain, but points outside it are not covered
+    assert_eq!(sparse_min.query(-1001), None);
+    assert_eq!(sparse_max.query(1001), None);
+}
+
+#[test]
+#[should_panic(expected = "a Li Chao tree needs at least one coordinate")]
+fn li_chao_tree_empty() {
+    LiChaoTree::new_min(&[]);
+}
+
+#[test]
+#[should_panic(expected = "empty domain [5, 4]")]
+fn li_chao_tree_sparse_empty() {
+    LiChaoTreeSparse::new_max(5, 4);
+}
+
+#[test]
+fn sparse_table() {
+    let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+    for n in 1..=50 {
+        let arr: Vec<i64> = (0..n).map(|_| rng.gen_range(-100..=100)).collect();
+        let table_min: SparseTable<MinNode> = SparseTable::new(&arr);
+        let table_max: SparseTable<MaxNode> = SparseTable::new(&arr);
+        let table_sum: DisjointSparseTable<SumNode> = DisjointSparseTable::new(&arr);
+        let table_subarray: DisjointSparseTable<MaxSubarrayNode> = DisjointSparseTable::new(&arr);
+        assert_eq!(table_min.len(), n);
+        assert_eq!(table_sum.len(), n);
+
+        for left in 0..n {
+            for right in left..n {
+                let range = &arr[left..=right];
+                let min = *range.iter().min().unwrap();
+                let max = *range.iter().max().unwrap();
+                let min_pos = left + range.iter().position(|x| *x == min).unwrap();
+                let max_pos = left + range.iter().position(|x| *x == max).unwrap();
+                assert_eq!(table_min.query(left, right), (min, min_pos));
+                assert_eq!(table_max.query(left, right), (max, max_pos));
+                assert_eq!(table_sum.query(left, right), range.iter().sum::<i64>());
+                assert_eq!(table_subarray.query(left, right).best, max_subarray(range));
+            }
+        }
+
+        // Multiples of small numbers so that ranges have non-trivial gcds
+        let arr: Vec<u64> = (0..n)
+            .map(|_| rng.gen_range(1..=6) * rng.gen_range(1..=30))
+            .collect();
+        let table_gcd: SparseTable<GcdNode> = SparseTable::new(&arr);
+        let table_and: SparseTable<AndNode> = SparseTable::new(&arr);
+        for left in 0..n {
+            for right in left..n {
+                let range = &arr[left..=right];
+                assert_eq!(
+                    table_gcd.query(left, right),
+                    range.iter().fold(0, |a, b| gcd(a, *b))
+                );
+                assert_eq!(
+                    table_and.query(left, right),
+                    range.iter().fold(u64::MAX, |a, b| a & b)
+                );
+            }
+        }
+    }
+}
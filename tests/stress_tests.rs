@@ -3,6 +3,7 @@ use rand::Rng;
 use rand::SeedableRng;
 use rust_ds::SegmentTree;
 use rust_ds::SegmentTreeBmp;
+use rust_ds::SegmentTreeBottomUp;
 use rust_ds::SumNode;
 use std::time::Duration;
 use std::time::Instant;
@@ -30,12 +31,12 @@ fn gen_test_input(seed: u64, n: usize, m: usize) -> TestInput {
     let mut rng: StdRng = SeedableRng::seed_from_u64(seed);
 
     fn gen_arr_elem(rng: &mut StdRng, range_min: i64, range_max: i64) -> i64 {
-        return rng.gen_range(range_min..=range_max);
+        rng.gen_range(range_min..=range_max)
     }
 
     fn gen_operation(rng: &mut StdRng, n: usize, range_min: i64, range_max: i64) -> Operation {
-        let left: usize = rng.gen_range(0..n) as usize;
-        let right: usize = rng.gen_range(left..n) as usize;
+        let left: usize = rng.gen_range(0..n);
+        let right: usize = rng.gen_range(left..n);
         let tp = rng.gen_range(0..=1);
 
         if tp == 0 {
@@ -64,7 +65,7 @@ fn process_input(input: &TestInput) {
     for operation in &input.operations {
         match operation {
             Operation::Update { left, right, delta } => {
-                st.update(*left, *right, &delta);
+                st.update(*left, *right, delta);
             }
             Operation::Query { left, right } => {
                 let _ = st.query(*left, *right);
@@ -78,7 +79,21 @@ fn process_input_bump(input: &TestInput) {
     for operation in &input.operations {
         match operation {
             Operation::Update { left, right, delta } => {
-                st.update(*left, *right, &delta);
+                st.update(*left, *right, delta);
+            }
+            Operation::Query { left, right } => {
+                let _ = st.query(*left, *right);
+            }
+        }
+    }
+}
+
+fn process_input_bottom_up(input: &TestInput) {
+    let mut st: SegmentTreeBottomUp<SumNode> = SegmentTreeBottomUp::new(&input.arr);
+    for operation in &input.operations {
+        match operation {
+            Operation::Update { left, right, delta } => {
+                st.update(*left, *right, delta);
             }
             Operation::Query { left, right } => {
                 let _ = st.query(*left, *right);
@@ -93,8 +108,7 @@ fn segment_tree_regular() {
     let m = 100000;
     let num_iterations: usize = 1000;
 
-    let mut elapsed_times: Vec<Duration> = Vec::new();
-    elapsed_times.reserve((num_iterations + 1).try_into().unwrap());
+    let mut elapsed_times: Vec<Duration> = Vec::with_capacity(num_iterations + 1);
 
     for seed in 0..num_iterations {
         let input = gen_test_input(seed.try_into().unwrap(), n, m);
@@ -108,7 +122,7 @@ fn segment_tree_regular() {
 
     println!(
         "Median Elapsed Regular: {:.2?}",
-        elapsed_times[(num_iterations / 2) as usize],
+        elapsed_times[num_iterations / 2],
     );
 }
 
@@ -118,8 +132,7 @@ fn segment_tree_bump() {
     let m = 100000;
     let num_iterations: usize = 1000;
 
-    let mut elapsed_times: Vec<Duration> = Vec::new();
-    elapsed_times.reserve((num_iterations + 1).try_into().unwrap());
+    let mut elapsed_times: Vec<Duration> = Vec::with_capacity(num_iterations + 1);
 
     for seed in 0..num_iterations {
         let input = gen_test_input(seed.try_into().unwrap(), n, m);
@@ -133,6 +146,58 @@ fn segment_tree_bump() {
 
     println!(
         "Median Elapsed Bumped: {:.2?}",
-        elapsed_times[(num_iterations / 2) as usize]
+        elapsed_times[num_iterations / 2]
+    );
+}
+
+#[test]
+fn segment_tree_bottom_up() {
+    let n = 100000;
+    let m = 100000;
+    let num_iterations: usize = 1000;
+
+    let mut elapsed_times: Vec<Duration> = Vec::with_capacity(num_iterations + 1);
+
+    for seed in 0..num_iterations {
+        let input = gen_test_input(seed.try_into().unwrap(), n, m);
+
+        let timer = Instant::now();
+        process_input_bottom_up(&input);
+        let elapsed = timer.elapsed();
+        elapsed_times.push(elapsed);
+    }
+    elapsed_times.sort();
+
+    println!(
+        "Median Elapsed Bottom-up: {:.2?}",
+        elapsed_times[num_iterations / 2]
     );
 }
+
+#[test]
+fn segment_tree_compare() {
+    let num_iterations: usize = 100;
+
+    for seed in 0..num_iterations {
+        let n = seed + 1;
+        let input = gen_test_input(seed.try_into().unwrap(), n, 1000);
+
+        let mut st: SegmentTree<SumNode> = SegmentTree::new(&input.arr);
+        let mut st_bmp: SegmentTreeBmp<SumNode> = SegmentTreeBmp::new(&input.arr);
+        let mut st_bottom_up: SegmentTreeBottomUp<SumNode> = SegmentTreeBottomUp::new(&input.arr);
+        for operation in &input.operations {
+            match operation {
+                Operation::Update { left, right, delta } => {
+                    st.update(*left, *right, delta);
+                    st_bmp.update(*left, *right, delta);
+                    st_bottom_up.update(*left, *right, delta);
+                }
+                Operation::Query { left, right } => {
+                    let expected = st.query(*left, *right);
+                    assert_eq!(st_bmp.query(*left, *right), expected);
+                    assert_eq!(st_bottom_up.query(*left, *right), expected);
+                }
+            }
+        }
+    }
+}